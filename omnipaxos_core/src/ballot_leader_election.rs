@@ -4,11 +4,13 @@ use crate::util::defaults::*;
 #[allow(unused_imports)]
 use crate::utils::hocon_kv::LOG_FILE_PATH;
 
+#[cfg(feature = "decided_hash")]
+use crate::util::DecidedHash;
 #[cfg(feature = "logging")]
 use crate::utils::logger::create_logger;
 use crate::{
     messages::ballot_leader_election::{
-        BLEMessage, HeartbeatMsg, HeartbeatReply, HeartbeatRequest,
+        BLEMessage, HeartbeatMsg, HeartbeatReply, HeartbeatRequest, PreVoteReply, PreVoteRequest,
     },
     omni_paxos::OmniPaxosConfig,
     util::NodeId,
@@ -60,6 +62,15 @@ pub(crate) struct BallotLeaderElection {
     majority: usize,
     /// Vector which holds all the outgoing messages of the BLE instance.
     outgoing: Vec<BLEMessage>,
+    /// States if a majority must agree that the leader is missing before a new election is started.
+    pre_vote: bool,
+    /// States if the leader was heard from in the last heartbeat round.
+    leader_heard: bool,
+    /// The heartbeat round of the ongoing pre-vote and the peers that granted it.
+    pre_vote_grants: Option<(u32, Vec<NodeId>)>,
+    /// The decided hash of the local log that is piggybacked on heartbeat replies.
+    #[cfg(feature = "decided_hash")]
+    decided_hash: Option<DecidedHash>,
     /// Logger used to output the status of the component.
     #[cfg(feature = "logging")]
     logger: Logger,
@@ -85,6 +96,11 @@ impl BallotLeaderElection {
             quorum_connected: true,
             leader: config.initial_leader,
             outgoing: Vec::with_capacity(config.buffer_size),
+            pre_vote: config.pre_vote,
+            leader_heard: true,
+            pre_vote_grants: None,
+            #[cfg(feature = "decided_hash")]
+            decided_hash: None,
             #[cfg(feature = "logging")]
             logger: {
                 let path = config.logger_file_path;
@@ -110,6 +126,12 @@ impl BallotLeaderElection {
         self.current_ballot.priority = p;
     }
 
+    /// Update the decided hash that is sent in heartbeat replies.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn set_decided_hash(&mut self, h: Option<DecidedHash>) {
+        self.decided_hash = h;
+    }
+
     /// Returns outgoing messages
     pub(crate) fn get_outgoing_msgs(&mut self) -> Vec<BLEMessage> {
         std::mem::take(&mut self.outgoing)
@@ -122,6 +144,8 @@ impl BallotLeaderElection {
         match m.msg {
             HeartbeatMsg::Request(req) => self.handle_request(m.from, req),
             HeartbeatMsg::Reply(rep) => self.handle_reply(rep),
+            HeartbeatMsg::PreVoteRequest(req) => self.handle_pre_vote_request(m.from, req),
+            HeartbeatMsg::PreVoteReply(rep) => self.handle_pre_vote_reply(m.from, rep),
         }
     }
 
@@ -137,10 +161,10 @@ impl BallotLeaderElection {
         self.leader = Some(leader_ballot);
     }*/
 
-    fn check_leader(&mut self) -> Option<Ballot> {
+    fn check_leader(&mut self, accepting_leader: Option<Ballot>) -> Option<Ballot> {
         self.quorum_connected = true;
         let ballots = std::mem::take(&mut self.ballots);
-        let top_ballot = ballots
+        let candidates: Vec<Ballot> = ballots
             .into_iter()
             .filter_map(
                 |(ballot, candidate)| {
@@ -151,17 +175,47 @@ impl BallotLeaderElection {
                     }
                 },
             )
-            .max()
-            .unwrap_or_default();
+            .collect();
+        let top_ballot = candidates.iter().max().copied().unwrap_or_default();
+        // with pre-vote, a greater ballot does not replace an established leader that was heard from in this round
+        let keep_leader = self.pre_vote
+            && self.leader == accepting_leader
+            && self.leader.is_some_and(|l| candidates.contains(&l));
 
         if top_ballot < self.leader.unwrap_or_default() {
             // did not get HB from leader
-            self.current_ballot.n = self.leader.unwrap_or_default().n + 1;
-            self.leader = None;
+            self.leader_heard = false;
+            if self.pre_vote {
+                self.start_pre_vote();
+            } else {
+                self.abandon_leader();
+            }
             None
+        } else if self.leader != Some(top_ballot) && keep_leader {
+            self.leader_heard = true;
+            self.pre_vote_grants = None;
+            match self.leader {
+                Some(l) if l.pid == self.pid && top_ballot.pid != self.pid => {
+                    // outbid the greater ballot, so that its server rejoins this leader instead of taking over
+                    self.current_ballot.n = top_ballot.n + 1;
+                    self.leader = Some(self.current_ballot);
+                    #[cfg(feature = "logging")]
+                    debug!(
+                        self.logger,
+                        "BLE {}, Kept leadership over {:?} with: {:?}",
+                        self.pid,
+                        top_ballot,
+                        self.current_ballot
+                    );
+                    self.leader
+                }
+                _ => None,
+            }
         } else if self.leader != Some(top_ballot) {
             // got a new leader with greater ballot
             self.leader = Some(top_ballot);
+            self.leader_heard = true;
+            self.pre_vote_grants = None;
             #[cfg(feature = "logging")]
             debug!(
                 self.logger,
@@ -169,10 +223,53 @@ impl BallotLeaderElection {
             );
             Some(top_ballot)
         } else {
+            self.leader_heard = true;
+            self.pre_vote_grants = None;
             None
         }
     }
 
+    /// Returns true if a `Prepare` with ballot `n` must not be promised, because pre-vote is enabled and this server keeps
+    /// following an established leader with a smaller ballot on another server, which was heard from in the last heartbeat round.
+    /// `accepting_leader` is the leader that Sequence Paxos is in the Accept phase with, if any.
+    pub(crate) fn keeps_leader_over(&self, n: Ballot, accepting_leader: Option<Ballot>) -> bool {
+        self.pre_vote
+            && self.leader_heard
+            && self.leader == accepting_leader
+            && self.leader.is_some_and(|l| l < n && l.pid != n.pid)
+    }
+
+    /// Stops following the current leader and increments the ballot to start a new election.
+    fn abandon_leader(&mut self) {
+        self.current_ballot.n = self.leader.unwrap_or_default().n + 1;
+        self.leader = None;
+        self.pre_vote_grants = None;
+    }
+
+    /// Asks the peers whether they have also stopped hearing from the leader.
+    fn start_pre_vote(&mut self) {
+        let leader = self.leader.unwrap_or_default();
+        #[cfg(feature = "logging")]
+        debug!(
+            self.logger,
+            "BLE {}, Missed leader {:?}, starting pre-vote in round {}",
+            self.pid,
+            leader,
+            self.hb_round
+        );
+        self.pre_vote_grants = Some((self.hb_round, vec![]));
+        for peer in &self.peers {
+            self.outgoing.push(BLEMessage {
+                from: self.pid,
+                to: *peer,
+                msg: HeartbeatMsg::PreVoteRequest(PreVoteRequest {
+                    round: self.hb_round,
+                    leader,
+                }),
+            });
+        }
+    }
+
     /// Initiates a new heartbeat round.
     pub(crate) fn new_hb_round(&mut self) {
         self.hb_round += 1;
@@ -196,7 +293,9 @@ impl BallotLeaderElection {
         }
     }
 
-    pub(crate) fn hb_timeout(&mut self) -> Option<Ballot> {
+    /// Completes the current heartbeat round and returns the newly elected leader, if any.
+    /// `accepting_leader` is the leader that Sequence Paxos is in the Accept phase with, if any.
+    pub(crate) fn hb_timeout(&mut self, accepting_leader: Option<Ballot>) -> Option<Ballot> {
         let result: Option<Ballot> = if self.ballots.len() + 1 >= self.majority {
             #[cfg(feature = "logging")]
             debug!(
@@ -205,7 +304,7 @@ impl BallotLeaderElection {
             );
             self.ballots
                 .push((self.current_ballot, self.quorum_connected));
-            self.check_leader()
+            self.check_leader(accepting_leader)
         } else {
             #[cfg(feature = "logging")]
             warn!(
@@ -227,6 +326,8 @@ impl BallotLeaderElection {
             round: req.round,
             ballot: self.current_ballot,
             quorum_connected: self.quorum_connected,
+            #[cfg(feature = "decided_hash")]
+            decided_hash: self.decided_hash,
         };
 
         self.outgoing.push(BLEMessage {
@@ -236,6 +337,41 @@ impl BallotLeaderElection {
         });
     }
 
+    fn handle_pre_vote_request(&mut self, from: u64, req: PreVoteRequest) {
+        // a requester that missed an older leader than ours has not heard of our leader yet
+        let granted = (self.leader.is_none() || !self.leader_heard)
+            && req.leader >= self.leader.unwrap_or_default();
+        #[cfg(feature = "logging")]
+        debug!(
+            self.logger,
+            "BLE {}, Pre-vote from {} for missing leader {:?}, granted: {}",
+            self.pid,
+            from,
+            req.leader,
+            granted
+        );
+        self.outgoing.push(BLEMessage {
+            from: self.pid,
+            to: from,
+            msg: HeartbeatMsg::PreVoteReply(PreVoteReply {
+                round: req.round,
+                granted,
+            }),
+        });
+    }
+
+    fn handle_pre_vote_reply(&mut self, from: u64, rep: PreVoteReply) {
+        if let Some((round, grants)) = self.pre_vote_grants.as_mut() {
+            if rep.round == *round && rep.granted && !grants.contains(&from) {
+                grants.push(from);
+                // +1 for our own vote
+                if grants.len() + 1 >= self.majority {
+                    self.abandon_leader();
+                }
+            }
+        }
+    }
+
     fn handle_reply(&mut self, rep: HeartbeatReply) {
         if rep.round == self.hb_round {
             self.ballots.push((rep.ballot, rep.quorum_connected));
@@ -260,6 +396,7 @@ impl BallotLeaderElection {
 /// * `logger`: Custom logger for logging events of Ballot Leader Election.
 /// * `logger_file_path`: The path where the default logger logs events.
 /// * `buffer_size`: The buffer size for outgoing messages.
+/// * `pre_vote`: If a majority must agree that the leader is missing before a new election is started.
 #[derive(Clone, Debug)]
 pub(crate) struct BLEConfig {
     pid: NodeId,
@@ -267,6 +404,7 @@ pub(crate) struct BLEConfig {
     priority: u64,
     initial_leader: Option<Ballot>,
     buffer_size: usize,
+    pre_vote: bool,
     #[cfg(feature = "logging")]
     logger: Option<Logger>,
     #[cfg(feature = "logging")]
@@ -281,6 +419,7 @@ impl From<OmniPaxosConfig> for BLEConfig {
             priority: config.leader_priority,
             initial_leader: config.initial_leader,
             buffer_size: BLE_BUFFER_SIZE,
+            pre_vote: config.pre_vote,
             #[cfg(feature = "logging")]
             logger: None,
             #[cfg(feature = "logging")]
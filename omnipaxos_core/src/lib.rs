@@ -9,6 +9,7 @@
 //! * `latest_accepted` - Only send latest accepted log index as all preceding entries are implicitly accepted. Reduces message overhead.
 //! * `latest_decide` - Only send latest decided log index as all preceding entries are implicitly decided. Reduces message overhead.
 //! * `continued_leader_reconfiguration` - Let the cluster pick the current leader as the initial leader in the new configuration (if possible) to shorten down-time during reconfiguration.
+//! * `decided_hash` - Maintain a hash of the decided log that is exchanged in heartbeats to detect diverging logs between servers. Enabled per server with `OmniPaxosConfig::build_with_decided_hash()`, which requires log entries to implement `Hash`.
 
 #![deny(missing_docs)]
 /// Trait and struct related to the leader election in Omni-Paxos.
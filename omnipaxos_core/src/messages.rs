@@ -6,6 +6,8 @@ use crate::{
 
 /// Internal component for log replication
 pub mod sequence_paxos {
+    #[cfg(feature = "decided_hash")]
+    use crate::util::DecidedHash;
     use crate::{
         ballot_leader_election::Ballot,
         storage::{Entry, Snapshot, SnapshotType, StopSign},
@@ -13,6 +15,20 @@ pub mod sequence_paxos {
     };
     use std::fmt::Debug;
 
+    /// A client proposal forwarded towards the leader.
+    #[derive(Clone, Debug)]
+    pub struct ForwardedProposal<T>
+    where
+        T: Entry,
+    {
+        /// The proposed entry.
+        pub entry: T,
+        /// The server that the entry was proposed at.
+        pub origin: NodeId,
+        /// The number of election timeouts left until the proposal is failed if it is still pending.
+        pub remaining: u64,
+    }
+
     /// Prepare message sent by a newly-elected leader to initiate the Prepare phase.
     #[derive(Copy, Clone, Debug)]
     pub struct Prepare {
@@ -47,6 +63,9 @@ pub mod sequence_paxos {
         pub accepted_idx: u64,
         /// The StopSign accepted by this follower
         pub stopsign: Option<StopSign>,
+        /// The decided hash of this follower. Adopted by the leader if it installs `decided_snapshot`.
+        #[cfg(feature = "decided_hash")]
+        pub decided_hash: Option<DecidedHash>,
     }
 
     /// AcceptSync message sent by the leader to synchronize the logs of all replicas in the prepare phase.
@@ -68,6 +87,9 @@ pub mod sequence_paxos {
         pub decided_idx: u64,
         /// StopSign to be accepted
         pub stopsign: Option<StopSign>,
+        /// The decided hash of the leader. Adopted by the follower if it installs `decided_snapshot`.
+        #[cfg(feature = "decided_hash")]
+        pub decided_hash: Option<DecidedHash>,
     }
 
     /// The first accept message sent. Only used by a pre-elected leader after reconfiguration.
@@ -159,7 +181,9 @@ pub mod sequence_paxos {
         Accepted(Accepted),
         Decide(Decide),
         /// Forward client proposals to the leader.
-        ProposalForward(Vec<T>),
+        ProposalForward(Vec<ForwardedProposal<T>>),
+        /// Return forwarded proposals that expired while pending to the server they were proposed at.
+        ProposalExpired(Vec<T>),
         Compaction(Compaction),
         AcceptStopSign(AcceptStopSign),
         AcceptedStopSign(AcceptedStopSign),
@@ -185,6 +209,8 @@ pub mod sequence_paxos {
 
 /// The different messages BLE uses to communicate with other replicas.
 pub mod ballot_leader_election {
+    #[cfg(feature = "decided_hash")]
+    use crate::util::DecidedHash;
     use crate::{ballot_leader_election::Ballot, util::NodeId};
 
     /// An enum for all the different BLE message types.
@@ -193,6 +219,8 @@ pub mod ballot_leader_election {
     pub enum HeartbeatMsg {
         Request(HeartbeatRequest),
         Reply(HeartbeatReply),
+        PreVoteRequest(PreVoteRequest),
+        PreVoteReply(PreVoteReply),
     }
 
     /// Requests a reply from all the other replicas.
@@ -211,6 +239,27 @@ pub mod ballot_leader_election {
         pub ballot: Ballot,
         /// States if the replica is a candidate to become a leader.
         pub quorum_connected: bool,
+        /// The decided hash of the replica. Used to detect diverging logs.
+        #[cfg(feature = "decided_hash")]
+        pub decided_hash: Option<DecidedHash>,
+    }
+
+    /// Asks the other replicas whether they have also stopped hearing from the leader, before a new election is started.
+    #[derive(Clone, Debug)]
+    pub struct PreVoteRequest {
+        /// Number of the heartbeat round in which the leader was missed.
+        pub round: u32,
+        /// Ballot of the leader that was missed.
+        pub leader: Ballot,
+    }
+
+    /// Replies to a `PreVoteRequest`.
+    #[derive(Clone, Debug)]
+    pub struct PreVoteReply {
+        /// Number of the heartbeat round of the request.
+        pub round: u32,
+        /// States if the replica has also stopped hearing from the leader and agrees to a new election.
+        pub granted: bool,
     }
 
     /// A struct for a Paxos message that also includes sender and receiver.
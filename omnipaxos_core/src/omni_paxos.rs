@@ -2,13 +2,26 @@
 use crate::utils::hocon_kv::*;
 use crate::{
     ballot_leader_election::{Ballot, BallotLeaderElection},
-    messages::Message,
+    messages::{
+        sequence_paxos::{Compaction, PaxosMsg},
+        Message,
+    },
     sequence_paxos::SequencePaxos,
     storage::{Entry, Snapshot, StopSign, Storage},
-    util::{defaults::BUFFER_SIZE, LogEntry, NodeId},
+    util::{
+        defaults::{self, BUFFER_SIZE},
+        LogEntry, NodeId, QuorumProgress,
+    },
+};
+#[cfg(feature = "decided_hash")]
+use crate::{
+    messages::ballot_leader_election::HeartbeatMsg,
+    util::{hash_entry, DecidedHash},
 };
 #[cfg(feature = "hocon_config")]
 use hocon::Hocon;
+#[cfg(feature = "decided_hash")]
+use std::hash::Hash;
 use std::ops::RangeBounds;
 
 /// Configuration for `OmniPaxos`.
@@ -17,6 +30,9 @@ use std::ops::RangeBounds;
 /// * `pid`: The unique identifier of this node. Must not be 0.
 /// * `peers`: The peers of this node i.e. the `pid`s of the other replicas in the configuration.
 /// * `buffer_size`: The buffer size for outgoing messages.
+/// * `error_buffer_size`: The maximum number of error events buffered until they are fetched with `error_events()`.
+/// * `max_pending_proposal_age`: The maximum number of `election_timeout()` calls a proposal is kept pending while there is no leader to handle it. Overdue proposals are failed with `ProposeErr::Timeout`.
+/// * `pre_vote`: If enabled, a server only starts a new election if a majority of its peers have also stopped hearing from the leader.
 /// * `skip_prepare_use_leader`: The initial leader of the cluster. Could be used in combination with reconfiguration to skip the prepare phase in the new configuration.
 /// * `logger`: Custom logger for logging events of Sequence Paxos.
 /// * `logger_file_path`: The path where the default logger logs events.
@@ -27,11 +43,14 @@ pub struct OmniPaxosConfig {
     pub pid: NodeId,
     pub peers: Vec<u64>,
     pub buffer_size: usize,
+    pub error_buffer_size: usize,
+    pub max_pending_proposal_age: u64,
     pub skip_prepare_use_leader: Option<Ballot>,
     pub logger_file_path: Option<String>,
     /*** BLE config fields ***/
     pub leader_priority: u64,
     pub initial_leader: Option<Ballot>,
+    pub pre_vote: bool,
     #[cfg(feature = "logging")]
     pub logger_path: Option<String>,
 }
@@ -58,9 +77,18 @@ impl OmniPaxosConfig {
         if let Some(b) = h[BUFFER_SIZE].as_i64() {
             config.buffer_size = b as usize;
         }
+        if let Some(b) = h[ERROR_BUFFER_SIZE].as_i64() {
+            config.error_buffer_size = b as usize;
+        }
+        if let Some(a) = h[MAX_PENDING_PROPOSAL_AGE].as_i64() {
+            config.max_pending_proposal_age = a as u64;
+        }
         if let Some(p) = h[PRIORITY].as_i64().map(|p| p as u64) {
             config.leader_priority = p;
         }
+        if let Some(p) = h[PRE_VOTE].as_bool() {
+            config.pre_vote = p;
+        }
 
         config.logger_file_path = h[LOG_FILE_PATH].as_string();
         config
@@ -81,6 +109,14 @@ impl OmniPaxosConfig {
             "Peers should not include self pid"
         );
         assert!(self.buffer_size > 0, "Buffer size must be greater than 0");
+        assert!(
+            self.error_buffer_size > 0,
+            "Error buffer size must be greater than 0"
+        );
+        assert!(
+            self.max_pending_proposal_age > 0,
+            "Max pending proposal age must be greater than 0"
+        );
         if let Some(x) = self.skip_prepare_use_leader {
             assert_ne!(x.pid, 0, "Initial leader cannot be 0")
         };
@@ -89,6 +125,20 @@ impl OmniPaxosConfig {
             ble: BallotLeaderElection::with(self.into()),
         }
     }
+
+    /// Checks all configurations and returns the local OmniPaxos node that also maintains a hash of its decided log.
+    /// See [`OmniPaxos::get_decided_hash`].
+    #[cfg(feature = "decided_hash")]
+    pub fn build_with_decided_hash<T, S, B>(self, storage: B) -> OmniPaxos<T, S, B>
+    where
+        T: Entry + Hash,
+        S: Snapshot<T>,
+        B: Storage<T, S>,
+    {
+        let mut omni_paxos = self.build(storage);
+        omni_paxos.seq_paxos.enable_decided_hash(hash_entry::<T>);
+        omni_paxos
+    }
 }
 
 impl Default for OmniPaxosConfig {
@@ -98,10 +148,13 @@ impl Default for OmniPaxosConfig {
             pid: 0,
             peers: Vec::new(),
             buffer_size: BUFFER_SIZE,
+            error_buffer_size: defaults::ERROR_BUFFER_SIZE,
+            max_pending_proposal_age: defaults::MAX_PENDING_PROPOSAL_AGE,
             skip_prepare_use_leader: None,
             logger_file_path: None,
             leader_priority: 0,
             initial_leader: None,
+            pre_vote: true,
             #[cfg(feature = "logging")]
             logger_path: None,
         }
@@ -190,6 +243,17 @@ where
         ble_msgs.chain(paxos_msgs).collect()
     }
 
+    /// Returns the errors that occurred since the last call, e.g. while handling incoming messages, and therefore could not be returned to a caller.
+    /// At most `error_buffer_size` errors are buffered between calls, any further errors are dropped and counted in [`Self::get_num_dropped_error_events`].
+    pub fn error_events(&mut self) -> Vec<NodeError> {
+        self.seq_paxos.get_error_events()
+    }
+
+    /// Returns the total number of error events that were dropped because the error buffer was full.
+    pub fn get_num_dropped_error_events(&self) -> u64 {
+        self.seq_paxos.get_num_dropped_error_events()
+    }
+
     /// Read entry at index `idx` in the log. Returns `None` if `idx` is out of bounds.
     pub fn read(&self, idx: u64) -> Option<LogEntry<T, S>> {
         match self.seq_paxos.internal_storage.read(idx..idx + 1) {
@@ -216,11 +280,52 @@ where
     /// Handle an incoming message.
     pub fn handle_incoming(&mut self, m: Message<T, S>) {
         match m {
-            Message::SequencePaxos(p) => self.seq_paxos.handle(p),
-            Message::BLE(b) => self.ble.handle(b),
+            Message::SequencePaxos(p) => {
+                // do not promise a greater ballot while the current leader is still heard from, see `pre_vote`
+                if let PaxosMsg::Prepare(prep) = &p.msg {
+                    let accepting_leader = self.seq_paxos.get_accepting_leader();
+                    if self.ble.keeps_leader_over(prep.n, accepting_leader) {
+                        return;
+                    }
+                }
+                self.seq_paxos.handle(p)
+            }
+            Message::BLE(b) => {
+                #[cfg(feature = "decided_hash")]
+                match &b.msg {
+                    HeartbeatMsg::Request(_) => {
+                        self.ble.set_decided_hash(self.seq_paxos.get_decided_hash())
+                    }
+                    HeartbeatMsg::Reply(rep) => {
+                        self.seq_paxos.check_decided_hash(b.from, rep.decided_hash)
+                    }
+                    _ => {}
+                }
+                self.ble.handle(b)
+            }
         }
     }
 
+    /// Returns the hash of the decided log, or `None` if it could not be computed, e.g. if the log was compacted without a stored hash.
+    /// The hash is only maintained by servers created with [`OmniPaxosConfig::build_with_decided_hash`].
+    #[cfg(feature = "decided_hash")]
+    pub fn get_decided_hash(&self) -> Option<DecidedHash> {
+        self.seq_paxos.get_decided_hash()
+    }
+
+    /// Returns the hash of the decided log up to `idx`. Returns `None` if `idx` is not decided yet or if the entries needed to compute it have been compacted.
+    /// The hash is recomputed from the nearest hash stored at or below `idx`. These are stored every 1000 decided entries, so about as many entries are rehashed.
+    /// For an `idx` decided before the last recovery, the log is instead rehashed from the start, which costs O(`idx`).
+    #[cfg(feature = "decided_hash")]
+    pub fn decided_hash_at(&self, idx: u64) -> Option<DecidedHash> {
+        self.seq_paxos.get_decided_hash_at(idx)
+    }
+
+    /// Returns the progress of the current round: for the leader, which servers have responded in the current phase and which are missing; for a follower, its promised and accepted ballots.
+    pub fn quorum_progress(&self) -> QuorumProgress {
+        self.seq_paxos.get_quorum_progress()
+    }
+
     /// Returns whether this Sequence Paxos has been reconfigured
     pub fn is_reconfigured(&self) -> Option<StopSign> {
         self.seq_paxos.is_reconfigured()
@@ -231,6 +336,27 @@ where
         self.seq_paxos.append(entry)
     }
 
+    /// Append an entry to the replicated log. If the entry is still pending after `timeout` calls to [`Self::election_timeout`], e.g. because there is no leader, it is failed with `ProposeErr::Timeout`.
+    /// The timeout is capped by `max_pending_proposal_age`.
+    pub fn append_with_timeout(&mut self, entry: T, timeout: u64) -> Result<(), ProposeErr<T>> {
+        self.seq_paxos.append_with_timeout(entry, timeout)
+    }
+
+    /// Returns the proposals that expired while pending since the last call. Each proposal is returned as a `ProposeErr::Timeout`.
+    pub fn expired_proposals(&mut self) -> Vec<ProposeErr<T>> {
+        self.seq_paxos.get_expired_proposals()
+    }
+
+    /// Returns the number of proposals that are pending, i.e. waiting for a leader to handle them.
+    pub fn get_num_pending_proposals(&self) -> usize {
+        self.seq_paxos.get_num_pending_proposals()
+    }
+
+    /// Returns the total number of proposals that expired while pending.
+    pub fn get_num_expired_proposals(&self) -> u64 {
+        self.seq_paxos.get_num_expired_proposals()
+    }
+
     /// Propose a reconfiguration. Returns error if already stopped or new configuration is empty.
     pub fn reconfigure(&mut self, rc: ReconfigurationRequest) -> Result<(), ProposeErr<T>> {
         self.seq_paxos.reconfigure(rc)
@@ -252,8 +378,10 @@ where
     /// It is also used for the election process, where the server checks if it can become the leader.
     /// This function should be called periodically to detect leader failure and drive the election process.
     /// For instance if `election_timeout()` is called every 100ms, then if the leader fails, the servers will detect it after 100ms and elect a new server after another 100ms if possible.
+    /// Pending proposals that are overdue are also expired here.
     pub fn election_timeout(&mut self) {
-        if let Some(b) = self.ble.hb_timeout() {
+        self.seq_paxos.expire_pending_proposals();
+        if let Some(b) = self.ble.hb_timeout(self.seq_paxos.get_accepting_leader()) {
             self.seq_paxos.handle_leader(b);
         }
     }
@@ -281,7 +409,7 @@ impl ReconfigurationRequest {
     }
 }
 
-/// An error returning the proposal that was failed due to that the current configuration is stopped.
+/// An error returning the proposal that was failed due to that the current configuration is stopped or that it timed out.
 #[allow(missing_docs)]
 #[derive(Debug)]
 pub enum ProposeErr<T>
@@ -290,6 +418,8 @@ where
 {
     Normal(T),
     Reconfiguration(Vec<NodeId>),
+    /// The proposal was pending for longer than its timeout or `max_pending_proposal_age`.
+    Timeout(T),
 }
 
 /// An error returning the proposal that was failed due to that the current configuration is stopped.
@@ -302,3 +432,25 @@ pub enum CompactionErr {
     /// Trim was called at a follower node. Trim must be called by the leader, which is the returned NodeId.
     NotCurrentLeader(NodeId),
 }
+
+/// An error that occurred without a caller to return it to, e.g. while handling an incoming message. Fetched with [`OmniPaxos::error_events`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum NodeError {
+    /// A compaction forwarded by a peer could not be performed locally. The affected entries are still kept in the log.
+    Compaction {
+        /// The peer that forwarded the compaction.
+        from: NodeId,
+        /// The forwarded compaction.
+        compaction: Compaction,
+        /// The reason the compaction failed.
+        err: CompactionErr,
+    },
+    /// The decided log of this server and `peer` diverge, i.e. the decided hashes at `idx` differ.
+    DecidedHashMismatch {
+        /// The peer whose decided log diverges.
+        peer: NodeId,
+        /// The decided index where the hashes were compared.
+        idx: u64,
+    },
+}
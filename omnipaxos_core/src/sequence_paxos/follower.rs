@@ -56,6 +56,8 @@ where
                 decided_idx,
                 accepted_idx,
                 stopsign: self.get_stopsign(),
+                #[cfg(feature = "decided_hash")]
+                decided_hash: self.internal_storage.get_decided_hash(),
             };
             self.outgoing.push(PaxosMessage {
                 from: self.pid,
@@ -80,6 +82,9 @@ where
                         }
                         _ => unimplemented!(),
                     }
+                    #[cfg(feature = "decided_hash")]
+                    self.internal_storage
+                        .adopt_decided_hash(accsync.decided_hash);
                     let accepted_idx = self.internal_storage.append_entries(accsync.suffix);
                     Accepted {
                         n: accsync.n,
@@ -44,6 +44,8 @@ where
                 accepted_idx,
                 suffix: vec![],
                 stopsign: self.get_stopsign(),
+                #[cfg(feature = "decided_hash")]
+                decided_hash: self.internal_storage.get_decided_hash(),
             };
             self.leader_state.set_promise(my_promise, self.pid, true);
             /* initialise longest chosen sequence and update state */
@@ -93,7 +95,7 @@ where
         }
     }
 
-    pub(crate) fn forward_proposals(&mut self, mut entries: Vec<T>) {
+    pub(crate) fn forward_proposals(&mut self, mut proposals: Vec<(T, u64, NodeId)>) {
         if self.leader.pid > 0 && self.leader.pid != self.pid {
             #[cfg(feature = "logging")]
             trace!(
@@ -101,7 +103,15 @@ where
                 "Forwarding proposal to Leader {:?}",
                 self.leader
             );
-            let pf = PaxosMsg::ProposalForward(entries);
+            let forwarded = proposals
+                .into_iter()
+                .map(|(entry, deadline, origin)| ForwardedProposal {
+                    entry,
+                    origin,
+                    remaining: deadline.saturating_sub(self.timeout_tick),
+                })
+                .collect();
+            let pf = PaxosMsg::ProposalForward(forwarded);
             let msg = PaxosMessage {
                 from: self.pid,
                 to: self.leader.pid,
@@ -109,7 +119,7 @@ where
             };
             self.outgoing.push(msg);
         } else {
-            self.pending_proposals.append(&mut entries);
+            self.pending_proposals.append(&mut proposals);
         }
     }
 
@@ -133,16 +143,24 @@ where
         }
     }
 
-    pub(crate) fn handle_forwarded_proposal(&mut self, mut entries: Vec<T>) {
+    pub(crate) fn handle_forwarded_proposal(&mut self, proposals: Vec<ForwardedProposal<T>>) {
         if !self.stopped() {
             match self.state {
-                (Role::Leader, Phase::Prepare) => self.pending_proposals.append(&mut entries),
-                (Role::Leader, Phase::Accept) => self.send_batch_accept(entries),
+                (Role::Leader, Phase::Prepare) => {
+                    let mut proposals = self.with_remaining_time(proposals);
+                    self.pending_proposals.append(&mut proposals);
+                }
+                (Role::Leader, Phase::Accept) => {
+                    self.send_batch_accept(proposals.into_iter().map(|p| p.entry).collect())
+                }
                 (Role::Leader, Phase::FirstAccept) => {
                     self.send_first_accept();
-                    self.send_batch_accept(entries);
+                    self.send_batch_accept(proposals.into_iter().map(|p| p.entry).collect());
+                }
+                _ => {
+                    let proposals = self.with_remaining_time(proposals);
+                    self.forward_proposals(proposals);
                 }
-                _ => self.forward_proposals(entries),
             }
         }
     }
@@ -313,6 +331,8 @@ where
             sync_idx,
             decided_idx: my_decided_idx,
             stopsign: self.get_stopsign(),
+            #[cfg(feature = "decided_hash")]
+            decided_hash: self.internal_storage.get_decided_hash(),
         };
         let msg = PaxosMessage {
             from: self.pid,
@@ -330,7 +350,10 @@ where
 
     fn append_pending_proposals(&mut self) {
         if !self.pending_proposals.is_empty() {
-            let new_entries = std::mem::take(&mut self.pending_proposals);
+            let new_entries = std::mem::take(&mut self.pending_proposals)
+                .into_iter()
+                .map(|(entry, _, _)| entry)
+                .collect();
             // append new proposals in my sequence
             let accepted_idx = self.internal_storage.append_entries(new_entries);
             self.leader_state.set_accepted_idx(self.pid, accepted_idx);
@@ -357,6 +380,8 @@ where
                             .leader_state
                             .get_decided_idx(max_promise_meta.pid)
                             .unwrap();
+                        #[cfg(feature = "decided_hash")]
+                        let decided_hash = max_promise_meta.decided_hash;
                         match s {
                             SnapshotType::Complete(c) => {
                                 self.internal_storage.set_snapshot(decided_idx, c);
@@ -366,6 +391,8 @@ where
                             }
                             _ => unimplemented!(),
                         }
+                        #[cfg(feature = "decided_hash")]
+                        self.internal_storage.adopt_decided_hash(decided_hash);
                         self.internal_storage.append_entries(suffix);
                         if let Some(ss) = max_stopsign {
                             self.accept_stopsign(ss);
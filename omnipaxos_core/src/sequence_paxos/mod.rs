@@ -4,15 +4,17 @@ use super::{
     storage::{Entry, Snapshot, StopSign, StopSignEntry, Storage},
     util::{defaults::BUFFER_SIZE, LeaderState},
 };
+#[cfg(feature = "decided_hash")]
+use crate::util::DecidedHash;
 #[cfg(feature = "logging")]
 use crate::utils::logger::create_logger;
 use crate::{
-    omni_paxos::{CompactionErr, OmniPaxosConfig, ProposeErr, ReconfigurationRequest},
+    omni_paxos::{CompactionErr, NodeError, OmniPaxosConfig, ProposeErr, ReconfigurationRequest},
     storage::InternalStorage,
-    util::{ConfigurationId, NodeId},
+    util::{ConfigurationId, NodeId, QuorumProgress, RoundPhase},
 };
 #[cfg(feature = "logging")]
-use slog::{debug, info, trace, Logger};
+use slog::{debug, info, trace, warn, Logger};
 use std::{fmt::Debug, marker::PhantomData, vec};
 
 pub mod follower;
@@ -33,12 +35,21 @@ where
     peers: Vec<u64>, // excluding self pid
     state: (Role, Phase),
     leader: Ballot,
-    pending_proposals: Vec<T>,
+    pending_proposals: Vec<(T, u64, NodeId)>, // (entry, deadline tick, origin)
+    expired_proposals: Vec<ProposeErr<T>>,
+    num_expired_proposals: u64,
+    max_pending_proposal_age: u64,
+    timeout_tick: u64, // number of election timeouts, in which the deadlines are counted
     pending_stopsign: Option<StopSign>,
     outgoing: Vec<PaxosMessage<T, S>>,
     leader_state: LeaderState<T, S>,
     latest_accepted_meta: Option<(Ballot, usize)>,
     buffer_size: usize,
+    errors: Vec<NodeError>,
+    error_buffer_size: usize,
+    num_dropped_errors: u64,
+    #[cfg(feature = "decided_hash")]
+    reported_hash_mismatches: Vec<Option<u64>>, // decided idx of the last reported mismatch, per pid
     s: PhantomData<S>,
     #[cfg(feature = "logging")]
     logger: Logger,
@@ -90,12 +101,21 @@ where
             peers,
             state,
             pending_proposals: vec![],
+            expired_proposals: vec![],
+            num_expired_proposals: 0,
+            max_pending_proposal_age: config.max_pending_proposal_age,
+            timeout_tick: 0,
             pending_stopsign: None,
             leader,
             outgoing: Vec::with_capacity(BUFFER_SIZE),
             leader_state: LeaderState::<T, S>::with(leader, lds, max_pid, majority),
             latest_accepted_meta: None,
             buffer_size: config.buffer_size,
+            errors: vec![],
+            error_buffer_size: config.error_buffer_size,
+            num_dropped_errors: 0,
+            #[cfg(feature = "decided_hash")]
+            reported_hash_mismatches: vec![None; max_pid],
             s: PhantomData,
             #[cfg(feature = "logging")]
             logger: {
@@ -198,23 +218,136 @@ where
         }
     }
 
-    fn handle_compaction(&mut self, c: Compaction) {
-        // try trimming and snapshotting forwarded compaction. Errors are ignored as that the data will still be kept.
-        match c {
-            Compaction::Trim(idx) => {
-                let _ = self.internal_storage.try_trim(idx);
-            }
-            Compaction::Snapshot(idx) => {
-                let _ = self.snapshot(idx, true);
+    fn handle_compaction(&mut self, c: Compaction, from: NodeId) {
+        // try trimming and snapshotting forwarded compaction. Errors are only reported as the data will still be kept.
+        let result = match c {
+            Compaction::Trim(idx) => self.internal_storage.try_trim(idx),
+            Compaction::Snapshot(idx) => self.snapshot(idx, true),
+        };
+        if let Err(err) = result {
+            self.report_error(NodeError::Compaction {
+                from,
+                compaction: c,
+                err,
+            });
+        }
+    }
+
+    /// Buffers an error that has no caller to be returned to. Drops the error if the buffer is full.
+    fn report_error(&mut self, e: NodeError) {
+        #[cfg(feature = "logging")]
+        warn!(self.logger, "Error event: {:?}", e);
+        if self.errors.len() < self.error_buffer_size {
+            self.errors.push(e);
+        } else {
+            self.num_dropped_errors += 1;
+        }
+    }
+
+    /// Reports a [`NodeError::DecidedHashMismatch`] if the decided hash of `from` differs from ours at the same index.
+    /// As the hash is chained, a divergence is only reported once per peer until the hashes match again.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn check_decided_hash(&mut self, from: NodeId, peer_hash: Option<DecidedHash>) {
+        let peer_hash = match peer_hash {
+            Some(h) => h,
+            None => return,
+        };
+        // avoid rehashing the log from the start on every heartbeat
+        if self
+            .internal_storage
+            .get_hash_checkpoint(peer_hash.idx)
+            .is_none()
+        {
+            return;
+        }
+        let reported = &mut self.reported_hash_mismatches[from as usize - 1];
+        match self.internal_storage.get_decided_hash_at(peer_hash.idx) {
+            Some(my_hash) if my_hash == peer_hash => *reported = None,
+            // implied by a mismatch already reported at an earlier index
+            Some(_) if matches!(reported, Some(idx) if *idx <= peer_hash.idx) => {}
+            Some(_) => {
+                *reported = Some(peer_hash.idx);
+                self.report_error(NodeError::DecidedHashMismatch {
+                    peer: from,
+                    idx: peer_hash.idx,
+                })
             }
+            None => {}
         }
     }
 
+    /// Starts maintaining the hash of the decided log with `entry_hasher`.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn enable_decided_hash(&mut self, entry_hasher: fn(&T) -> u64) {
+        self.internal_storage.enable_decided_hash(entry_hasher);
+    }
+
+    /// Returns the hash of the decided log.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn get_decided_hash(&self) -> Option<DecidedHash> {
+        self.internal_storage.get_decided_hash()
+    }
+
+    /// Returns the hash of the decided log at `idx`.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn get_decided_hash_at(&self, idx: u64) -> Option<DecidedHash> {
+        self.internal_storage.get_decided_hash_at(idx)
+    }
+
+    /// Returns the buffered error events.
+    pub(crate) fn get_error_events(&mut self) -> Vec<NodeError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Returns the number of error events that were dropped due to a full buffer.
+    pub(crate) fn get_num_dropped_error_events(&self) -> u64 {
+        self.num_dropped_errors
+    }
+
     /// Returns the id of the current leader.
     pub(crate) fn get_current_leader(&self) -> Ballot {
         self.leader
     }
 
+    /// Returns the ballot of the leader if this server is in the Accept phase with it, as the leader or a follower.
+    pub(crate) fn get_accepting_leader(&self) -> Option<Ballot> {
+        match self.state.1 {
+            Phase::Accept => Some(self.leader),
+            _ => None,
+        }
+    }
+
+    /// Returns the progress of the current round towards a quorum.
+    pub(crate) fn get_quorum_progress(&self) -> QuorumProgress {
+        match &self.state {
+            (Role::Leader, phase) => {
+                let phase = match phase {
+                    Phase::Prepare => RoundPhase::Prepare,
+                    _ => RoundPhase::Accept,
+                };
+                let mut nodes = self.peers.clone();
+                nodes.push(self.pid);
+                nodes.sort_unstable();
+                let accepted_idx = self.internal_storage.get_log_len();
+                let (responded, missing) = nodes
+                    .into_iter()
+                    .partition(|pid| self.leader_state.has_responded(*pid, phase, accepted_idx));
+                QuorumProgress::Leader {
+                    ballot: self.leader_state.n_leader,
+                    phase,
+                    quorum: self.leader_state.majority,
+                    responded,
+                    missing,
+                }
+            }
+            (Role::Follower, _) => QuorumProgress::Follower {
+                promised: self.internal_storage.get_promise(),
+                accepted_round: self.internal_storage.get_accepted_round(),
+                accepted_idx: self.internal_storage.get_log_len(),
+            },
+        }
+    }
+
     /// Returns the outgoing messages from this replica. The messages should then be sent via the network implementation.
     pub(crate) fn get_outgoing_msgs(&mut self) -> Vec<PaxosMessage<T, S>> {
         let mut outgoing = Vec::with_capacity(self.buffer_size);
@@ -243,7 +376,8 @@ where
             PaxosMsg::Accepted(accepted) => self.handle_accepted(accepted, m.from),
             PaxosMsg::Decide(d) => self.handle_decide(d),
             PaxosMsg::ProposalForward(proposals) => self.handle_forwarded_proposal(proposals),
-            PaxosMsg::Compaction(c) => self.handle_compaction(c),
+            PaxosMsg::ProposalExpired(entries) => self.handle_expired_proposals(entries),
+            PaxosMsg::Compaction(c) => self.handle_compaction(c, m.from),
             PaxosMsg::AcceptStopSign(acc_ss) => self.handle_accept_stopsign(acc_ss),
             PaxosMsg::AcceptedStopSign(acc_ss) => self.handle_accepted_stopsign(acc_ss, m.from),
             PaxosMsg::DecideStopSign(d_ss) => self.handle_decide_stopsign(d_ss),
@@ -269,11 +403,101 @@ where
         if self.stopped() {
             Err(ProposeErr::Normal(entry))
         } else {
-            self.propose_entry(entry);
+            self.propose_entry(entry, None);
             Ok(())
         }
     }
 
+    /// Append an entry to the replicated log. The entry is failed if it is still pending after `timeout` election timeouts.
+    pub(crate) fn append_with_timeout(
+        &mut self,
+        entry: T,
+        timeout: u64,
+    ) -> Result<(), ProposeErr<T>> {
+        if self.stopped() {
+            Err(ProposeErr::Normal(entry))
+        } else {
+            self.propose_entry(entry, Some(timeout));
+            Ok(())
+        }
+    }
+
+    /// Called on every election timeout. Fails the pending proposals that are overdue. Forwarded proposals are returned to the server they were proposed at.
+    pub(crate) fn expire_pending_proposals(&mut self) {
+        self.timeout_tick += 1;
+        if self.pending_proposals.is_empty() {
+            return;
+        }
+        let (expired, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending_proposals)
+            .into_iter()
+            .partition(|(_, deadline, _)| *deadline <= self.timeout_tick);
+        self.pending_proposals = pending;
+        let mut forwarded: Vec<(NodeId, Vec<T>)> = vec![];
+        let mut local = vec![];
+        for (entry, _, origin) in expired {
+            if origin == self.pid {
+                local.push(entry);
+            } else {
+                match forwarded.iter_mut().find(|(pid, _)| *pid == origin) {
+                    Some((_, entries)) => entries.push(entry),
+                    None => forwarded.push((origin, vec![entry])),
+                }
+            }
+        }
+        for (origin, entries) in forwarded {
+            self.outgoing.push(PaxosMessage {
+                from: self.pid,
+                to: origin,
+                msg: PaxosMsg::ProposalExpired(entries),
+            });
+        }
+        self.handle_expired_proposals(local);
+    }
+
+    fn handle_expired_proposals(&mut self, entries: Vec<T>) {
+        if !entries.is_empty() {
+            #[cfg(feature = "logging")]
+            debug!(self.logger, "Expired {} pending proposals", entries.len());
+            self.num_expired_proposals += entries.len() as u64;
+            self.expired_proposals
+                .extend(entries.into_iter().map(ProposeErr::Timeout));
+        }
+    }
+
+    /// Returns the proposals that expired since the last call.
+    pub(crate) fn get_expired_proposals(&mut self) -> Vec<ProposeErr<T>> {
+        std::mem::take(&mut self.expired_proposals)
+    }
+
+    /// Returns the number of pending proposals.
+    pub(crate) fn get_num_pending_proposals(&self) -> usize {
+        self.pending_proposals.len()
+    }
+
+    /// Returns the total number of proposals that expired while pending.
+    pub(crate) fn get_num_expired_proposals(&self) -> u64 {
+        self.num_expired_proposals
+    }
+
+    /// Attaches the deadline at which `entry` is failed if it is still pending. The deadline is capped by `max_pending_proposal_age`.
+    fn with_deadline(&self, entry: T, timeout: Option<u64>, origin: NodeId) -> (T, u64, NodeId) {
+        let timeout = timeout.map_or(self.max_pending_proposal_age, |t| {
+            t.min(self.max_pending_proposal_age)
+        });
+        (entry, self.timeout_tick + timeout, origin)
+    }
+
+    /// Keeps the deadlines of forwarded proposals, so that they are not extended on every hop.
+    fn with_remaining_time(&self, proposals: Vec<ForwardedProposal<T>>) -> Vec<(T, u64, NodeId)> {
+        proposals
+            .into_iter()
+            .map(|p| {
+                let remaining = p.remaining.min(self.max_pending_proposal_age);
+                (p.entry, self.timeout_tick + remaining, p.origin)
+            })
+            .collect()
+    }
+
     /// Propose a reconfiguration. Returns error if already stopped or new configuration is empty.
     pub(crate) fn reconfigure(&mut self, rc: ReconfigurationRequest) -> Result<(), ProposeErr<T>> {
         let ReconfigurationRequest {
@@ -362,15 +586,21 @@ where
         });
     }
 
-    fn propose_entry(&mut self, entry: T) {
+    fn propose_entry(&mut self, entry: T, timeout: Option<u64>) {
         match self.state {
-            (Role::Leader, Phase::Prepare) => self.pending_proposals.push(entry),
+            (Role::Leader, Phase::Prepare) => {
+                let proposal = self.with_deadline(entry, timeout, self.pid);
+                self.pending_proposals.push(proposal);
+            }
             (Role::Leader, Phase::Accept) => self.send_accept(entry),
             (Role::Leader, Phase::FirstAccept) => {
                 self.send_first_accept();
                 self.send_accept(entry);
             }
-            _ => self.forward_proposals(vec![entry]),
+            _ => {
+                let proposal = self.with_deadline(entry, timeout, self.pid);
+                self.forward_proposals(vec![proposal]);
+            }
         }
     }
 
@@ -404,6 +634,8 @@ enum Role {
 /// * `pid`: The unique identifier of this node. Must not be 0.
 /// * `peers`: The peers of this node i.e. the `pid`s of the other replicas in the configuration.
 /// * `buffer_size`: The buffer size for outgoing messages.
+/// * `error_buffer_size`: The buffer size for error events.
+/// * `max_pending_proposal_age`: The maximum number of election timeouts a proposal is kept pending.
 /// * `skip_prepare_use_leader`: The initial leader of the cluster. Could be used in combination with reconfiguration to skip the prepare phase in the new configuration.
 /// * `logger`: Custom logger for logging events of Sequence Paxos.
 /// * `logger_file_path`: The path where the default logger logs events.
@@ -413,6 +645,8 @@ pub struct SequencePaxosConfig {
     pid: NodeId,
     peers: Vec<u64>,
     buffer_size: usize,
+    error_buffer_size: usize,
+    max_pending_proposal_age: u64,
     skip_prepare_use_leader: Option<Ballot>,
     #[cfg(feature = "logging")]
     logger_file_path: Option<String>,
@@ -425,6 +659,8 @@ impl From<OmniPaxosConfig> for SequencePaxosConfig {
             pid: config.pid,
             peers: config.peers,
             buffer_size: config.buffer_size,
+            error_buffer_size: config.error_buffer_size,
+            max_pending_proposal_age: config.max_pending_proposal_age,
             skip_prepare_use_leader: config.skip_prepare_use_leader,
             #[cfg(feature = "logging")]
             logger_file_path: config.logger_file_path,
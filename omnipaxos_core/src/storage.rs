@@ -1,7 +1,9 @@
 use super::ballot_leader_election::Ballot;
+#[cfg(feature = "decided_hash")]
+use crate::util::defaults::DECIDED_HASH_PERSIST_INTERVAL;
 use crate::{
     omni_paxos::CompactionErr,
-    util::{ConfigurationId, IndexEntry, LogEntry, NodeId, SnapshottedEntry},
+    util::{ConfigurationId, DecidedHash, IndexEntry, LogEntry, NodeId, SnapshottedEntry},
 };
 use std::{
     fmt::Debug,
@@ -150,6 +152,14 @@ where
 
     /// Returns the stored snapshot.
     fn get_snapshot(&self) -> Option<S>;
+
+    /// Sets the hash of the decided log. Only used with the `decided_hash` feature, where it is called periodically so that the hash does not have to be recomputed over the whole log after recovery.
+    fn set_decided_hash(&mut self, _hash: DecidedHash) {}
+
+    /// Returns the stored hash of the decided log.
+    fn get_decided_hash(&self) -> Option<DecidedHash> {
+        None
+    }
 }
 
 #[allow(missing_docs)]
@@ -177,6 +187,15 @@ where
     S: Snapshot<T>,
 {
     storage: I,
+    /// Hashes a single entry. `None` if the decided hash is not maintained.
+    #[cfg(feature = "decided_hash")]
+    entry_hasher: Option<fn(&T) -> u64>,
+    /// The hash of the decided log. `None` if it could not be computed, e.g. when the log was compacted without a stored hash.
+    #[cfg(feature = "decided_hash")]
+    decided_hash: Option<DecidedHash>,
+    /// The hashes written to `storage` since it was loaded, in increasing order of index. The decided hash at an index is computed from the nearest one below it.
+    #[cfg(feature = "decided_hash")]
+    hash_checkpoints: Vec<DecidedHash>,
     _t: PhantomData<T>,
     _i: PhantomData<S>,
 }
@@ -190,6 +209,12 @@ where
     pub(crate) fn with(storage: I) -> Self {
         InternalStorage {
             storage,
+            #[cfg(feature = "decided_hash")]
+            entry_hasher: None,
+            #[cfg(feature = "decided_hash")]
+            decided_hash: None,
+            #[cfg(feature = "decided_hash")]
+            hash_checkpoints: vec![],
             _t: Default::default(),
             _i: Default::default(),
         }
@@ -381,7 +406,9 @@ where
     }
 
     pub(crate) fn set_decided_idx(&mut self, ld: u64) {
-        self.storage.set_decided_idx(ld)
+        self.storage.set_decided_idx(ld);
+        #[cfg(feature = "decided_hash")]
+        self.update_decided_hash(ld);
     }
 
     pub(crate) fn get_decided_idx(&self) -> u64 {
@@ -485,6 +512,8 @@ where
         } else {
             let decided_idx = self.storage.get_decided_idx();
             if idx <= decided_idx {
+                #[cfg(feature = "decided_hash")]
+                self.persist_decided_hash();
                 self.storage.trim(idx - compacted_idx);
                 self.storage.set_compacted_idx(idx);
                 Ok(())
@@ -511,9 +540,122 @@ where
             None => decided_idx,
         };
         if idx > self.get_compacted_idx() {
+            #[cfg(feature = "decided_hash")]
+            self.persist_decided_hash();
             let snapshot = self.create_snapshot(idx);
             self.set_snapshot(idx, snapshot);
         }
         Ok(())
     }
+
+    /*** Decided hash ***/
+    /// Starts maintaining the decided hash with `entry_hasher`. Loads the stored decided hash and extends it with the entries decided after it was stored.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn enable_decided_hash(&mut self, entry_hasher: fn(&T) -> u64) {
+        self.entry_hasher = Some(entry_hasher);
+        let stored_hash = match self.storage.get_decided_hash() {
+            Some(h) => Some(h),
+            None if self.get_compacted_idx() == 0 => Some(DecidedHash::default()),
+            None => None, // the compacted entries cannot be hashed anymore
+        };
+        self.decided_hash = stored_hash;
+        self.hash_checkpoints = stored_hash.into_iter().collect();
+        self.update_decided_hash(self.get_decided_idx());
+    }
+
+    /// Extends the decided hash with the entries decided up to `ld`.
+    #[cfg(feature = "decided_hash")]
+    fn update_decided_hash(&mut self, ld: u64) {
+        let (current, entry_hasher) = match (self.decided_hash, self.entry_hasher) {
+            (Some(h), Some(hasher)) if h.idx < ld => (h, hasher),
+            _ => return,
+        };
+        if current.idx < self.get_compacted_idx() {
+            // entries were compacted before they could be hashed
+            self.decided_hash = None;
+            return;
+        }
+        // a decided StopSign is not part of the entries
+        let to_idx = ld.min(self.get_log_len());
+        let entries = self.get_entries(current.idx, to_idx);
+        let new_hash = entries
+            .iter()
+            .fold(current, |h, e| h.chain(entry_hasher(e)));
+        self.decided_hash = Some(new_hash);
+        let persisted_idx = self
+            .hash_checkpoints
+            .last()
+            .map(|h| h.idx)
+            .unwrap_or_default();
+        if new_hash.idx >= persisted_idx + DECIDED_HASH_PERSIST_INTERVAL {
+            self.persist_decided_hash();
+        }
+    }
+
+    #[cfg(feature = "decided_hash")]
+    fn persist_decided_hash(&mut self) {
+        if let Some(h) = self.decided_hash {
+            if self.hash_checkpoints.last() != Some(&h) {
+                self.storage.set_decided_hash(h);
+                // the entries before the compacted index cannot be rehashed anymore
+                let compacted_idx = self.get_compacted_idx();
+                self.hash_checkpoints.retain(|c| c.idx >= compacted_idx);
+                self.hash_checkpoints.push(h);
+            }
+        }
+    }
+
+    /// Adopts the decided hash of the server whose snapshot was installed, as the snapshotted entries cannot be hashed locally.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn adopt_decided_hash(&mut self, hash: Option<DecidedHash>) {
+        if self.entry_hasher.is_none() {
+            return;
+        }
+        match (self.decided_hash, hash) {
+            (Some(current), Some(h)) if current.idx >= h.idx => {}
+            (_, Some(h)) => {
+                self.decided_hash = Some(h);
+                self.persist_decided_hash();
+            }
+            (_, None) => {
+                if self.decided_hash.map(|h| h.idx).unwrap_or_default() < self.get_compacted_idx() {
+                    self.decided_hash = None;
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn get_decided_hash(&self) -> Option<DecidedHash> {
+        self.decided_hash
+    }
+
+    /// Returns the decided hash at `idx`. Returns `None` if `idx` is not decided or the entries needed to compute it have been compacted.
+    /// Rehashes the entries from the nearest checkpoint below `idx`, or from the start of the log if `idx` is below the first checkpoint.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn get_decided_hash_at(&self, idx: u64) -> Option<DecidedHash> {
+        let current = self.decided_hash?;
+        let entry_hasher = self.entry_hasher?;
+        if idx == current.idx {
+            return Some(current);
+        } else if idx > current.idx {
+            return None;
+        }
+        let from = self.get_hash_checkpoint(idx).unwrap_or_default();
+        if from.idx < self.get_compacted_idx() {
+            return None;
+        }
+        let entries = self.get_entries(from.idx, idx);
+        if entries.len() as u64 != idx - from.idx {
+            return None;
+        }
+        Some(entries.iter().fold(from, |h, e| h.chain(entry_hasher(e))))
+    }
+
+    /// Returns the nearest checkpoint at or below `idx`. Computing the decided hash at an index without one requires rehashing the log from the start.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn get_hash_checkpoint(&self, idx: u64) -> Option<DecidedHash> {
+        let num_below = self.hash_checkpoints.partition_point(|c| c.idx <= idx);
+        num_below.checked_sub(1).map(|i| self.hash_checkpoints[i])
+    }
 }
@@ -12,6 +12,8 @@ pub(crate) struct PromiseMetaData {
     pub accepted_idx: u64,
     pub pid: NodeId,
     pub stopsign: Option<StopSign>,
+    #[cfg(feature = "decided_hash")]
+    pub decided_hash: Option<DecidedHash>,
 }
 
 impl PartialOrd for PromiseMetaData {
@@ -98,6 +100,8 @@ where
             accepted_idx: prom.accepted_idx,
             pid: from,
             stopsign: prom.stopsign,
+            #[cfg(feature = "decided_hash")]
+            decided_hash: prom.decided_hash,
         };
         if check_max_prom && promise_meta > self.max_promise_meta {
             self.max_promise_meta = promise_meta.clone();
@@ -166,6 +170,17 @@ where
         self.accepted_indexes[Self::pid_to_idx(pid)] = idx;
     }
 
+    /// In the Accept phase, a server has responded if it has accepted all of the `leader_accepted_idx` entries in the leader's log.
+    pub fn has_responded(&self, pid: NodeId, phase: RoundPhase, leader_accepted_idx: u64) -> bool {
+        match phase {
+            RoundPhase::Prepare => self.promises_meta[Self::pid_to_idx(pid)].is_some(),
+            RoundPhase::Accept => {
+                pid == self.n_leader.pid
+                    || self.accepted_indexes[Self::pid_to_idx(pid)] >= leader_accepted_idx
+            }
+        }
+    }
+
     #[cfg(feature = "batch_accept")]
     pub fn get_batch_accept_meta(&self, pid: NodeId) -> Option<(Ballot, usize)> {
         self.batch_accept_meta
@@ -241,6 +256,42 @@ where
     StopSign(StopSign),
 }
 
+/// The phase of the round that the leader is driving.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RoundPhase {
+    /// The leader is collecting `Promise`s.
+    Prepare,
+    /// The leader is collecting `Accepted`s.
+    Accept,
+}
+
+/// The progress of the current round of this server. Used to debug why a round does not complete.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuorumProgress {
+    /// The view of the leader of the round.
+    Leader {
+        /// The ballot of the round.
+        ballot: Ballot,
+        /// The phase of the round.
+        phase: RoundPhase,
+        /// The number of servers that must respond in the phase.
+        quorum: usize,
+        /// The servers that have responded in the phase, including the leader. In the Accept phase, these are the servers that have accepted all entries in the log of the leader.
+        responded: Vec<NodeId>,
+        /// The servers that have not responded in the phase.
+        missing: Vec<NodeId>,
+    },
+    /// The view of a follower.
+    Follower {
+        /// The highest ballot that this server has promised.
+        promised: Ballot,
+        /// The ballot in which this server last accepted entries.
+        accepted_round: Ballot,
+        /// The length of the log of this server.
+        accepted_idx: u64,
+    },
+}
+
 /// Convenience struct for checking if a certain index exists, is compacted or is a StopSign.
 #[derive(Debug, Clone)]
 pub(crate) enum IndexEntry {
@@ -275,8 +326,117 @@ where
     }
 }
 
+/// The hash of the decided log up to (but excluding) `idx`. Used to cheaply detect diverging logs between servers.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecidedHash {
+    /// The decided index that the hash covers.
+    pub idx: u64,
+    /// The chained hash of all decided entries before `idx`.
+    pub hash: u64,
+}
+
+impl DecidedHash {
+    /// Creates a [`DecidedHash`].
+    pub fn with(idx: u64, hash: u64) -> Self {
+        Self { idx, hash }
+    }
+
+    /// Returns the hash of the log extended with an entry with hash `entry_hash` at index `self.idx`.
+    #[cfg(feature = "decided_hash")]
+    pub(crate) fn chain(&self, entry_hash: u64) -> Self {
+        use std::hash::Hasher;
+        let mut hasher = LogHasher::default();
+        hasher.write_u64(self.hash);
+        hasher.write_u64(self.idx);
+        hasher.write_u64(entry_hash);
+        Self {
+            idx: self.idx + 1,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+/// Hashes a single log entry for the decided hash.
+#[cfg(feature = "decided_hash")]
+pub(crate) fn hash_entry<T: std::hash::Hash>(entry: &T) -> u64 {
+    use std::hash::Hasher;
+    let mut hasher = LogHasher::default();
+    entry.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// FNV-1a hasher that, unlike the default hasher of `std`, produces the same hashes across processes, platforms and Rust versions.
+#[cfg(feature = "decided_hash")]
+struct LogHasher(u64);
+
+#[cfg(feature = "decided_hash")]
+impl Default for LogHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+#[cfg(feature = "decided_hash")]
+impl std::hash::Hasher for LogHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // integers are hashed in little-endian and `usize` as `u64` to be platform independent.
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64)
+    }
+}
+
 pub(crate) mod defaults {
     pub(crate) const BUFFER_SIZE: usize = 100000;
+    pub(crate) const ERROR_BUFFER_SIZE: usize = 100;
+    pub(crate) const MAX_PENDING_PROPOSAL_AGE: u64 = 600;
+    #[cfg(feature = "decided_hash")]
+    pub(crate) const DECIDED_HASH_PERSIST_INTERVAL: u64 = 1000;
     pub(crate) const BLE_BUFFER_SIZE: usize = 100;
 }
 
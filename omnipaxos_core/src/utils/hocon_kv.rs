@@ -7,6 +7,8 @@ pub const PID: &str = "pid";
 pub const PEERS: &str = "peers";
 /// The priority of this replica
 pub const PRIORITY: &str = "priority";
+/// Enables the pre-vote check before starting a new leader election.
+pub const PRE_VOTE: &str = "pre_vote";
 /// A fixed delay that is added to the current_delay. It is measured in ticks.
 pub const HB_DELAY: &str = "hb_delay";
 /// A factor used in the beginning for a shorter hb_delay.
@@ -15,3 +17,7 @@ pub const INITIAL_DELAY: &str = "initial_delay";
 pub const LOG_FILE_PATH: &str = "log_file_path";
 /// Size of buffer for outgoing messages in `SequencePaxos`.
 pub const BUFFER_SIZE: &str = "sp_buffer_size";
+/// Size of buffer for error events in `SequencePaxos`.
+pub const ERROR_BUFFER_SIZE: &str = "error_buffer_size";
+/// Maximum number of election timeouts a proposal is pending before it is failed.
+pub const MAX_PENDING_PROPOSAL_AGE: &str = "max_pending_proposal_age";
@@ -0,0 +1,207 @@
+#![cfg(feature = "decided_hash")]
+pub mod utils;
+
+use omnipaxos_core::{
+    omni_paxos::{NodeError, OmniPaxos},
+    storage::Storage,
+};
+use utils::{
+    create_node, create_node_config, create_temp_dir, exchange_messages, run_election_rounds,
+    LatestValue, StorageType, StorageTypeSelector, Value,
+};
+
+const NUM_NODES: u64 = 3;
+const NUM_PROPOSALS: u64 = 2500;
+const NUM_ELECTION_ROUNDS: usize = 3;
+const NUM_HEARTBEAT_ROUNDS: usize = 10;
+const CORRUPTED_PID: u64 = NUM_NODES;
+
+type TestOmniPaxos = OmniPaxos<Value, LatestValue, StorageType<Value, LatestValue>>;
+
+/// Verifies that all servers in a healthy cluster have the same decided hash.
+#[test]
+fn decided_hash_consensus_test() {
+    let mut nodes: Vec<TestOmniPaxos> = (1..=NUM_NODES)
+        .map(|pid| create_hashing_node(pid, StorageType::with(StorageTypeSelector::Memory, "")))
+        .collect();
+    elect_leader(&mut nodes);
+    propose_and_decide(&mut nodes);
+
+    let expected_hash = nodes[0].get_decided_hash().expect("No decided hash found");
+    assert_eq!(expected_hash.idx, NUM_PROPOSALS);
+    let expected_prefix_hash = nodes[0].decided_hash_at(NUM_PROPOSALS / 2);
+    assert!(expected_prefix_hash.is_some());
+    for node in nodes.iter_mut() {
+        assert_eq!(node.get_decided_idx(), NUM_PROPOSALS);
+        assert_eq!(node.get_decided_hash(), Some(expected_hash));
+        assert_eq!(
+            node.decided_hash_at(NUM_PROPOSALS / 2),
+            expected_prefix_hash
+        );
+        assert!(node.error_events().is_empty());
+    }
+
+    println!("Pass decided_hash_consensus");
+}
+
+/// Verifies that servers that are not built with `build_with_decided_hash` do not maintain a decided hash.
+#[test]
+fn decided_hash_disabled_test() {
+    let node = create_node(
+        1,
+        NUM_NODES,
+        StorageType::<Value, LatestValue>::with(StorageTypeSelector::Memory, ""),
+    );
+    assert_eq!(node.get_decided_hash(), None);
+    assert_eq!(node.decided_hash_at(0), None);
+
+    println!("Pass decided_hash_disabled");
+}
+
+/// Verifies that a server with a corrupted decided log is detected by its peers via the heartbeats.
+#[test]
+fn decided_hash_mismatch_test() {
+    let mut nodes = create_diverged_nodes();
+    // the heartbeat requests are sent when the nodes are created
+    exchange_messages(&mut nodes, |_| true);
+    check_diverged_peers(&mut nodes);
+
+    println!("Pass decided_hash_mismatch");
+}
+
+/// Verifies that a diverged peer is reported only once even though it keeps diverging in every heartbeat round.
+#[test]
+fn decided_hash_mismatch_reported_once_test() {
+    let mut nodes = create_diverged_nodes();
+    run_election_rounds(&mut nodes, NUM_HEARTBEAT_ROUNDS, |_| true);
+    check_diverged_peers(&mut nodes);
+
+    run_election_rounds(&mut nodes, NUM_HEARTBEAT_ROUNDS, |_| true);
+    for node in nodes.iter_mut() {
+        assert!(node.error_events().is_empty());
+    }
+
+    println!("Pass decided_hash_mismatch_reported_once");
+}
+
+/// Verifies that the decided hash at an index computed from the stored hashes is the same as when rehashing the log from the start.
+#[test]
+fn decided_hash_checkpoint_test() {
+    let mut nodes: Vec<TestOmniPaxos> = (1..=NUM_NODES)
+        .map(|pid| create_hashing_node(pid, StorageType::with(StorageTypeSelector::Memory, "")))
+        .collect();
+    elect_leader(&mut nodes);
+    propose_and_decide(&mut nodes);
+
+    // only has a stored hash at the start and the end of the log
+    let mut storage = StorageType::with(StorageTypeSelector::Memory, "");
+    storage.append_entries((0..NUM_PROPOSALS).map(Value).collect());
+    storage.set_decided_idx(NUM_PROPOSALS);
+    let rehashing_node = create_hashing_node(1, storage);
+    for idx in 0..=NUM_PROPOSALS {
+        assert_eq!(
+            nodes[0].decided_hash_at(idx),
+            rehashing_node.decided_hash_at(idx)
+        );
+    }
+
+    println!("Pass decided_hash_checkpoint");
+}
+
+/// Verifies that the decided hash is the same after recovering from persistent storage.
+#[test]
+fn decided_hash_recovery_test() {
+    let temp_dir_path = create_temp_dir();
+    let create_nodes = || -> Vec<TestOmniPaxos> {
+        (1..=NUM_NODES)
+            .map(|pid| {
+                let storage = StorageType::with(
+                    StorageTypeSelector::Persistent,
+                    &format!("{temp_dir_path}{pid}"),
+                );
+                create_hashing_node(pid, storage)
+            })
+            .collect()
+    };
+
+    let mut nodes = create_nodes();
+    elect_leader(&mut nodes);
+    propose_and_decide(&mut nodes);
+    let hashes: Vec<_> = nodes.iter().map(|n| n.get_decided_hash()).collect();
+    let prefix_hashes: Vec<_> = nodes
+        .iter()
+        .map(|n| n.decided_hash_at(NUM_PROPOSALS / 2))
+        .collect();
+    drop(nodes);
+
+    let recovered_nodes = create_nodes();
+    for (i, node) in recovered_nodes.iter().enumerate() {
+        assert!(hashes[i].is_some());
+        assert_eq!(node.get_decided_hash(), hashes[i]);
+        assert_eq!(node.decided_hash_at(NUM_PROPOSALS / 2), prefix_hashes[i]);
+    }
+
+    println!("Pass decided_hash_recovery");
+}
+
+fn create_hashing_node(pid: u64, storage: StorageType<Value, LatestValue>) -> TestOmniPaxos {
+    create_node_config(pid, NUM_NODES).build_with_decided_hash(storage)
+}
+
+fn elect_leader(nodes: &mut [TestOmniPaxos]) {
+    run_election_rounds(nodes, NUM_ELECTION_ROUNDS, |_| true);
+    assert!(
+        nodes[0].get_current_leader().is_some(),
+        "No leader has been elected!"
+    );
+}
+
+fn propose_and_decide(nodes: &mut [TestOmniPaxos]) {
+    for v in 0..NUM_PROPOSALS {
+        nodes[0].append(Value(v)).expect("Failed to append");
+        if v % 100 == 0 {
+            exchange_messages(nodes, |_| true);
+        }
+    }
+    exchange_messages(nodes, |_| true);
+}
+
+/// Creates nodes that have all decided `NUM_PROPOSALS` entries, where the log of `CORRUPTED_PID` diverges from the others.
+fn create_diverged_nodes() -> Vec<TestOmniPaxos> {
+    (1..=NUM_NODES)
+        .map(|pid| {
+            let mut storage = StorageType::with(StorageTypeSelector::Memory, "");
+            let mut entries: Vec<Value> = (0..NUM_PROPOSALS).map(Value).collect();
+            if pid == CORRUPTED_PID {
+                entries[(NUM_PROPOSALS / 2) as usize] = Value(0);
+            }
+            storage.append_entries(entries);
+            storage.set_decided_idx(NUM_PROPOSALS);
+            create_hashing_node(pid, storage)
+        })
+        .collect()
+}
+
+/// Checks that each node has reported exactly one mismatch for every peer whose log diverges from its own.
+fn check_diverged_peers(nodes: &mut [TestOmniPaxos]) {
+    for (pid, node) in (1..=NUM_NODES).zip(nodes.iter_mut()) {
+        let mut diverged_peers: Vec<u64> = node
+            .error_events()
+            .into_iter()
+            .map(|e| match e {
+                NodeError::DecidedHashMismatch { peer, idx } => {
+                    assert_eq!(idx, NUM_PROPOSALS);
+                    peer
+                }
+                e => panic!("{}", format!("Got unexpected error event: {:?}", e)),
+            })
+            .collect();
+        diverged_peers.sort_unstable();
+        if pid == CORRUPTED_PID {
+            let healthy_peers: Vec<u64> = (1..NUM_NODES).collect();
+            assert_eq!(diverged_peers, healthy_peers);
+        } else {
+            assert_eq!(diverged_peers, vec![CORRUPTED_PID]);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+pub mod utils;
+
+use omnipaxos_core::{
+    messages::{
+        sequence_paxos::{Compaction, PaxosMessage, PaxosMsg},
+        Message,
+    },
+    omni_paxos::{CompactionErr, NodeError, OmniPaxosConfig},
+};
+use omnipaxos_storage::memory_storage::MemoryStorage;
+use utils::{LatestValue, Value};
+
+/// Verifies that a forwarded compaction that cannot be performed locally
+/// is reported as an error event with the peer and compaction that caused it.
+#[test]
+fn forwarded_compaction_error_test() {
+    let mut op_config = OmniPaxosConfig::default();
+    op_config.pid = 2;
+    op_config.peers = vec![1, 3];
+    op_config.configuration_id = 1;
+    let mut paxos = op_config.build(MemoryStorage::<Value, LatestValue>::default());
+
+    paxos.handle_incoming(compaction_msg(1, 2, Compaction::Trim(10)));
+
+    let errors = paxos.error_events();
+    assert_eq!(errors.len(), 1, "Expected exactly one error event");
+    match errors.first().unwrap() {
+        NodeError::Compaction {
+            from,
+            compaction: Compaction::Trim(idx),
+            err: CompactionErr::UndecidedIndex(decided_idx),
+        } => {
+            assert_eq!(*from, 1);
+            assert_eq!(*idx, 10);
+            assert_eq!(*decided_idx, 0);
+        }
+        e => panic!("{}", format!("Got unexpected error event: {:?}", e)),
+    }
+    assert!(paxos.error_events().is_empty());
+    assert_eq!(paxos.get_num_dropped_error_events(), 0);
+
+    println!("Pass forwarded_compaction_error");
+}
+
+/// Verifies that error events exceeding `error_buffer_size` are dropped and counted.
+#[test]
+fn error_buffer_overflow_test() {
+    let error_buffer_size = 2;
+    let num_errors = 5;
+    let mut op_config = OmniPaxosConfig::default();
+    op_config.pid = 2;
+    op_config.peers = vec![1, 3];
+    op_config.configuration_id = 1;
+    op_config.error_buffer_size = error_buffer_size;
+    let mut paxos = op_config.build(MemoryStorage::<Value, LatestValue>::default());
+
+    for idx in 1..=num_errors {
+        paxos.handle_incoming(compaction_msg(1, 2, Compaction::Snapshot(Some(idx))));
+    }
+
+    assert_eq!(paxos.error_events().len(), error_buffer_size);
+    assert_eq!(
+        paxos.get_num_dropped_error_events(),
+        num_errors - error_buffer_size as u64
+    );
+
+    println!("Pass error_buffer_overflow");
+}
+
+fn compaction_msg(from: u64, to: u64, c: Compaction) -> Message<Value, LatestValue> {
+    Message::SequencePaxos(PaxosMessage {
+        from,
+        to,
+        msg: PaxosMsg::Compaction(c),
+    })
+}
@@ -0,0 +1,150 @@
+pub mod utils;
+
+use omnipaxos_core::{
+    messages::{sequence_paxos::PaxosMsg, Message},
+    omni_paxos::{OmniPaxos, OmniPaxosConfig, ProposeErr},
+    util::{QuorumProgress, RoundPhase},
+};
+use omnipaxos_storage::memory_storage::MemoryStorage;
+use utils::{create_node, exchange_messages, run_election_rounds, LatestValue, Value};
+
+const MAX_PENDING_PROPOSAL_AGE: u64 = 10;
+const PROPOSAL_TIMEOUT: u64 = 2;
+const FORWARDED_PROPOSAL_TIMEOUT: u64 = 5;
+const NUM_NODES: u64 = 3;
+const NUM_ELECTION_ROUNDS: usize = 3;
+
+type TestOmniPaxos = OmniPaxos<Value, LatestValue, MemoryStorage<Value, LatestValue>>;
+
+/// Verifies that proposals pending on a node without leader are failed with
+/// `ProposeErr::Timeout` once they exceed their timeout or the max pending age.
+#[test]
+fn expire_pending_proposals_test() {
+    let num_proposals = 10;
+    let mut op_config = OmniPaxosConfig::default();
+    op_config.pid = 1;
+    op_config.peers = vec![2, 3];
+    op_config.configuration_id = 1;
+    op_config.max_pending_proposal_age = MAX_PENDING_PROPOSAL_AGE;
+    // the peers are never connected, so this node stays without a leader.
+    let mut paxos = op_config.build(MemoryStorage::<Value, LatestValue>::default());
+
+    for v in 1..=num_proposals {
+        if v % 2 == 0 {
+            paxos
+                .append_with_timeout(Value(v), PROPOSAL_TIMEOUT)
+                .expect("Failed to append");
+        } else {
+            paxos.append(Value(v)).expect("Failed to append");
+        }
+    }
+    assert_eq!(paxos.get_num_pending_proposals(), num_proposals as usize);
+
+    // only the proposals with an explicit timeout are overdue
+    for _ in 1..PROPOSAL_TIMEOUT {
+        paxos.election_timeout();
+    }
+    assert!(paxos.expired_proposals().is_empty());
+    paxos.election_timeout();
+    let expired = expired_values(paxos.expired_proposals());
+    assert_eq!(
+        expired,
+        (1..=num_proposals)
+            .filter(|v| v % 2 == 0)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        paxos.get_num_pending_proposals(),
+        num_proposals as usize / 2
+    );
+
+    // the remaining proposals are failed after the max pending age
+    for _ in (PROPOSAL_TIMEOUT + 1)..MAX_PENDING_PROPOSAL_AGE {
+        paxos.election_timeout();
+    }
+    assert!(paxos.expired_proposals().is_empty());
+    paxos.election_timeout();
+    let expired = expired_values(paxos.expired_proposals());
+    assert_eq!(
+        expired,
+        (1..=num_proposals)
+            .filter(|v| v % 2 != 0)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(paxos.get_num_pending_proposals(), 0);
+    assert_eq!(paxos.get_num_expired_proposals(), num_proposals);
+    assert!(paxos.expired_proposals().is_empty());
+
+    println!("Pass expire_pending_proposals");
+}
+
+/// Verifies that a proposal forwarded to a leader that is stuck in the Prepare phase keeps the deadline
+/// it was proposed with, and that it is failed with `ProposeErr::Timeout` at the follower it was proposed at.
+#[test]
+fn expire_forwarded_proposal_test() {
+    // the promises never reach the leader, so it stays in the Prepare phase
+    let deliver = |msg: &Message<Value, LatestValue>| match msg {
+        Message::SequencePaxos(m) => !matches!(m.msg, PaxosMsg::Promise(_)),
+        Message::BLE(_) => true,
+    };
+    let mut nodes: Vec<TestOmniPaxos> = (1..=NUM_NODES)
+        .map(|pid| create_node(pid, NUM_NODES, MemoryStorage::default()))
+        .collect();
+    run_election_rounds(&mut nodes, NUM_ELECTION_ROUNDS, deliver);
+    let leader = nodes[0]
+        .get_current_leader()
+        .expect("No leader has been elected!");
+    let follower = (1..=NUM_NODES)
+        .find(|pid| *pid != leader)
+        .expect("No followers found!");
+    match nodes[(leader - 1) as usize].quorum_progress() {
+        QuorumProgress::Leader { phase, .. } => assert_eq!(phase, RoundPhase::Prepare),
+        p => panic!("{}", format!("Leader returned a follower view: {:?}", p)),
+    }
+
+    let proposal = 1;
+    nodes[(follower - 1) as usize]
+        .append_with_timeout(Value(proposal), FORWARDED_PROPOSAL_TIMEOUT)
+        .expect("Failed to append");
+    exchange_messages(&mut nodes, deliver);
+    assert_eq!(nodes[(leader - 1) as usize].get_num_pending_proposals(), 1);
+    assert_eq!(
+        nodes[(follower - 1) as usize].get_num_pending_proposals(),
+        0
+    );
+
+    // still pending before the deadline
+    run_election_rounds(
+        &mut nodes,
+        (FORWARDED_PROPOSAL_TIMEOUT - 1) as usize,
+        deliver,
+    );
+    assert_eq!(nodes[(leader - 1) as usize].get_num_pending_proposals(), 1);
+    assert!(nodes[(follower - 1) as usize]
+        .expired_proposals()
+        .is_empty());
+
+    // expired at the deadline, long before the max pending age
+    run_election_rounds(&mut nodes, 1, deliver);
+    let leader_node = &mut nodes[(leader - 1) as usize];
+    assert_eq!(leader_node.get_num_pending_proposals(), 0);
+    assert!(leader_node.expired_proposals().is_empty());
+    let follower_node = &mut nodes[(follower - 1) as usize];
+    assert_eq!(
+        expired_values(follower_node.expired_proposals()),
+        vec![proposal]
+    );
+    assert_eq!(follower_node.get_num_expired_proposals(), 1);
+
+    println!("Pass expire_forwarded_proposal");
+}
+
+fn expired_values(expired: Vec<ProposeErr<Value>>) -> Vec<u64> {
+    expired
+        .into_iter()
+        .map(|e| match e {
+            ProposeErr::Timeout(Value(v)) => v,
+            e => panic!("{}", format!("Got unexpected error: {:?}", e)),
+        })
+        .collect()
+}
@@ -0,0 +1,196 @@
+pub mod utils;
+
+use omnipaxos_core::{
+    messages::Message,
+    omni_paxos::OmniPaxos,
+    util::{NodeId, QuorumProgress},
+};
+use omnipaxos_storage::memory_storage::MemoryStorage;
+use utils::{
+    create_node, create_node_config, exchange_messages, run_election_rounds, LatestValue, Value,
+};
+
+const NUM_NODES: u64 = 3;
+const NUM_PROPOSALS: u64 = 10;
+const NUM_ELECTION_ROUNDS: usize = 3;
+const NUM_PARTITIONED_ROUNDS: usize = 20;
+/// The server with pre-vote disabled.
+const DISRUPTIVE_NODE: NodeId = 1;
+
+type TestOmniPaxos = OmniPaxos<Value, LatestValue, MemoryStorage<Value, LatestValue>>;
+
+/// Verifies that a follower that is partitioned from the leader, but not from the other follower,
+/// cannot disrupt the established leader, neither during the partition nor after it heals.
+#[test]
+fn partial_partition_pre_vote_test() {
+    let mut nodes: Vec<TestOmniPaxos> = (1..=NUM_NODES)
+        .map(|pid| create_node(pid, NUM_NODES, MemoryStorage::default()))
+        .collect();
+    let mut cut_links = vec![];
+    run_election_rounds(&mut nodes, NUM_ELECTION_ROUNDS, |m| {
+        is_connected(&cut_links, m)
+    });
+    let leader = nodes[0]
+        .get_current_leader()
+        .expect("No leader has been elected!");
+    let leader_ballot = nodes[0].get_current_leader_ballot();
+    let partitioned = (1..=NUM_NODES)
+        .find(|pid| *pid != leader)
+        .expect("No followers found!");
+
+    cut_links.push((leader, partitioned));
+    run_election_rounds(&mut nodes, NUM_PARTITIONED_ROUNDS, |m| {
+        is_connected(&cut_links, m)
+    });
+    for node in &nodes {
+        assert_eq!(node.get_current_leader_ballot(), leader_ballot);
+    }
+    for v in 1..=NUM_PROPOSALS {
+        nodes[(leader - 1) as usize]
+            .append(Value(v))
+            .expect("Failed to append");
+    }
+    exchange_messages(&mut nodes, |m| is_connected(&cut_links, m));
+
+    cut_links.clear();
+    nodes[(leader - 1) as usize].reconnected(partitioned);
+    nodes[(partitioned - 1) as usize].reconnected(leader);
+    run_election_rounds(&mut nodes, NUM_ELECTION_ROUNDS, |m| {
+        is_connected(&cut_links, m)
+    });
+    for node in &nodes {
+        assert_eq!(node.get_current_leader_ballot(), leader_ballot);
+        assert_eq!(node.get_decided_idx(), NUM_PROPOSALS);
+    }
+
+    println!("Pass partial_partition_pre_vote");
+}
+
+/// Verifies that a server with pre-vote disabled, which increments its ballot while it is partitioned from the leader,
+/// neither takes over from the leader during the partition nor when it rejoins with its greater ballot.
+#[test]
+fn rejoin_with_greater_ballot_pre_vote_test() {
+    let mut nodes: Vec<TestOmniPaxos> = (1..=NUM_NODES)
+        .map(|pid| {
+            let mut op_config = create_node_config(pid, NUM_NODES);
+            op_config.pre_vote = pid != DISRUPTIVE_NODE;
+            op_config.build(MemoryStorage::default())
+        })
+        .collect();
+    let mut cut_links = vec![];
+    run_election_rounds(&mut nodes, NUM_ELECTION_ROUNDS, |m| {
+        is_connected(&cut_links, m)
+    });
+    let leader = nodes[0]
+        .get_current_leader()
+        .expect("No leader has been elected!");
+    assert_ne!(leader, DISRUPTIVE_NODE);
+    let leader_ballot = nodes[0].get_current_leader_ballot();
+
+    cut_links.push((leader, DISRUPTIVE_NODE));
+    run_election_rounds(&mut nodes, NUM_PARTITIONED_ROUNDS, |m| {
+        is_connected(&cut_links, m)
+    });
+    // the disruptive server campaigns with a greater ballot, but the other follower keeps the leader
+    let disruptive_ballot = match nodes[(DISRUPTIVE_NODE - 1) as usize].quorum_progress() {
+        QuorumProgress::Leader {
+            ballot, responded, ..
+        } => {
+            assert!(Some(ballot) > leader_ballot);
+            assert_eq!(responded, vec![DISRUPTIVE_NODE]);
+            ballot
+        }
+        p => panic!("{}", format!("Disruptive server did not campaign: {:?}", p)),
+    };
+    for v in 1..=NUM_PROPOSALS {
+        nodes[(leader - 1) as usize]
+            .append(Value(v))
+            .expect("Failed to append");
+    }
+    exchange_messages(&mut nodes, |m| is_connected(&cut_links, m));
+    for node in nodes
+        .iter()
+        .filter(|n| n.get_current_leader() == Some(leader))
+    {
+        assert_eq!(node.get_current_leader_ballot(), leader_ballot);
+        assert_eq!(node.get_decided_idx(), NUM_PROPOSALS);
+    }
+
+    // the leader outbids the greater ballot when it hears it, and the disruptive server rejoins as a follower
+    cut_links.clear();
+    nodes[(leader - 1) as usize].reconnected(DISRUPTIVE_NODE);
+    nodes[(DISRUPTIVE_NODE - 1) as usize].reconnected(leader);
+    run_election_rounds(&mut nodes, NUM_ELECTION_ROUNDS, |m| {
+        is_connected(&cut_links, m)
+    });
+    for v in NUM_PROPOSALS + 1..=2 * NUM_PROPOSALS {
+        nodes[(leader - 1) as usize]
+            .append(Value(v))
+            .expect("Failed to append");
+    }
+    exchange_messages(&mut nodes, |m| is_connected(&cut_links, m));
+    for node in &nodes {
+        assert_eq!(node.get_current_leader(), Some(leader));
+        assert!(node.get_current_leader_ballot() > Some(disruptive_ballot));
+        assert_eq!(node.get_decided_idx(), 2 * NUM_PROPOSALS);
+    }
+
+    println!("Pass rejoin_with_greater_ballot_pre_vote");
+}
+
+/// Verifies that the failure of the leader still triggers a new election with pre-vote enabled.
+#[test]
+fn leader_failure_pre_vote_test() {
+    let mut nodes: Vec<TestOmniPaxos> = (1..=NUM_NODES)
+        .map(|pid| create_node(pid, NUM_NODES, MemoryStorage::default()))
+        .collect();
+    let mut cut_links = vec![];
+    run_election_rounds(&mut nodes, NUM_ELECTION_ROUNDS, |m| {
+        is_connected(&cut_links, m)
+    });
+    let failed_leader = nodes[0]
+        .get_current_leader()
+        .expect("No leader has been elected!");
+
+    // the failed leader can neither send nor receive any messages
+    for pid in (1..=NUM_NODES).filter(|pid| *pid != failed_leader) {
+        cut_links.push((failed_leader, pid));
+    }
+    // one round to detect the failure, one for the pre-vote and one to elect the new leader
+    run_election_rounds(&mut nodes, NUM_ELECTION_ROUNDS, |m| {
+        is_connected(&cut_links, m)
+    });
+    let followers: Vec<NodeId> = (1..=NUM_NODES)
+        .filter(|pid| *pid != failed_leader)
+        .collect();
+    let new_leader = nodes[(followers[0] - 1) as usize]
+        .get_current_leader()
+        .expect("No new leader has been elected!");
+    assert_ne!(new_leader, failed_leader);
+    for pid in &followers {
+        assert_eq!(
+            nodes[(*pid - 1) as usize].get_current_leader(),
+            Some(new_leader)
+        );
+    }
+
+    for v in 1..=NUM_PROPOSALS {
+        nodes[(new_leader - 1) as usize]
+            .append(Value(v))
+            .expect("Failed to append");
+    }
+    exchange_messages(&mut nodes, |m| is_connected(&cut_links, m));
+    for pid in &followers {
+        assert_eq!(nodes[(*pid - 1) as usize].get_decided_idx(), NUM_PROPOSALS);
+    }
+
+    println!("Pass leader_failure_pre_vote");
+}
+
+/// Returns whether `msg` is not sent over a link in `cut_links`, in either direction.
+fn is_connected(cut_links: &[(NodeId, NodeId)], msg: &Message<Value, LatestValue>) -> bool {
+    let (sender, receiver) = (msg.get_sender(), msg.get_receiver());
+    !cut_links
+        .iter()
+        .any(|l| *l == (sender, receiver) || *l == (receiver, sender))
+}
@@ -0,0 +1,156 @@
+pub mod utils;
+
+use omnipaxos_core::{
+    ballot_leader_election::Ballot,
+    messages::Message,
+    omni_paxos::OmniPaxos,
+    util::{NodeId, QuorumProgress, RoundPhase},
+};
+use omnipaxos_storage::memory_storage::MemoryStorage;
+use utils::{create_node, exchange_messages, run_election_rounds, LatestValue, Value};
+
+const NUM_NODES: u64 = 3;
+const NUM_PROPOSALS: u64 = 10;
+const NUM_ELECTION_ROUNDS: usize = 3;
+
+type TestOmniPaxos = OmniPaxos<Value, LatestValue, MemoryStorage<Value, LatestValue>>;
+
+/// Verifies that a follower whose replies never reach the leader is reported as missing,
+/// while the round still completes with the rest of the servers.
+#[test]
+fn blackholed_follower_quorum_progress_test() {
+    let blackholed = 1;
+    // the Sequence Paxos messages of `blackholed` are dropped, while its leader election messages are delivered
+    let deliver = |msg: &Message<Value, LatestValue>| match msg {
+        Message::SequencePaxos(m) => m.from != blackholed,
+        Message::BLE(_) => true,
+    };
+    let mut nodes: Vec<TestOmniPaxos> = (1..=NUM_NODES)
+        .map(|pid| create_node(pid, NUM_NODES, MemoryStorage::default()))
+        .collect();
+    run_election_rounds(&mut nodes, NUM_ELECTION_ROUNDS, deliver);
+    let leader = nodes[0]
+        .get_current_leader()
+        .expect("No leader has been elected!");
+    assert_ne!(leader, blackholed);
+    let leader_ballot = nodes[(leader - 1) as usize]
+        .get_current_leader_ballot()
+        .unwrap();
+
+    for v in 1..=NUM_PROPOSALS {
+        nodes[(leader - 1) as usize]
+            .append(Value(v))
+            .expect("Failed to append");
+    }
+    exchange_messages(&mut nodes, deliver);
+
+    let mut others: Vec<NodeId> = (1..=NUM_NODES).filter(|pid| *pid != blackholed).collect();
+    others.sort_unstable();
+    let leader_node = &nodes[(leader - 1) as usize];
+    assert_eq!(leader_node.get_decided_idx(), NUM_PROPOSALS);
+    match leader_node.quorum_progress() {
+        QuorumProgress::Leader {
+            ballot,
+            phase,
+            quorum,
+            responded,
+            missing,
+        } => {
+            assert_eq!(ballot, leader_ballot);
+            assert_eq!(phase, RoundPhase::Accept);
+            assert_eq!(quorum, (NUM_NODES / 2 + 1) as usize);
+            assert_eq!(responded, others);
+            assert_eq!(missing, vec![blackholed]);
+        }
+        p => panic!("{}", format!("Leader returned a follower view: {:?}", p)),
+    }
+    match nodes[(blackholed - 1) as usize].quorum_progress() {
+        QuorumProgress::Follower {
+            promised,
+            accepted_round,
+            accepted_idx,
+        } => {
+            // the follower has promised, but the leader never got the promise and did not sync it
+            assert_eq!(promised, leader_ballot);
+            assert_eq!(accepted_round, Ballot::default());
+            assert_eq!(accepted_idx, 0);
+        }
+        p => panic!("{}", format!("Follower returned a leader view: {:?}", p)),
+    }
+
+    println!("Pass blackholed_follower_quorum_progress");
+}
+
+/// Verifies that a follower that stops responding after a first batch has been decided
+/// is reported as missing once the leader has accepted entries that the follower has not.
+#[test]
+fn blackholed_after_decide_quorum_progress_test() {
+    let mut nodes: Vec<TestOmniPaxos> = (1..=NUM_NODES)
+        .map(|pid| create_node(pid, NUM_NODES, MemoryStorage::default()))
+        .collect();
+    run_election_rounds(&mut nodes, NUM_ELECTION_ROUNDS, |_| true);
+    let leader = nodes[0]
+        .get_current_leader()
+        .expect("No leader has been elected!");
+    let leader_ballot = nodes[(leader - 1) as usize]
+        .get_current_leader_ballot()
+        .unwrap();
+    let blackholed = (1..=NUM_NODES)
+        .find(|pid| *pid != leader)
+        .expect("No followers found!");
+
+    for v in 1..=NUM_PROPOSALS {
+        nodes[(leader - 1) as usize]
+            .append(Value(v))
+            .expect("Failed to append");
+    }
+    exchange_messages(&mut nodes, |_| true);
+    for node in &nodes {
+        assert_eq!(node.get_decided_idx(), NUM_PROPOSALS);
+    }
+
+    // the Sequence Paxos messages from and to `blackholed` are dropped, while its leader election messages are delivered
+    let deliver = |msg: &Message<Value, LatestValue>| match msg {
+        Message::SequencePaxos(m) => m.from != blackholed && m.to != blackholed,
+        Message::BLE(_) => true,
+    };
+    for v in NUM_PROPOSALS + 1..=2 * NUM_PROPOSALS {
+        nodes[(leader - 1) as usize]
+            .append(Value(v))
+            .expect("Failed to append");
+    }
+    exchange_messages(&mut nodes, deliver);
+
+    let others: Vec<NodeId> = (1..=NUM_NODES).filter(|pid| *pid != blackholed).collect();
+    let leader_node = &nodes[(leader - 1) as usize];
+    assert_eq!(leader_node.get_decided_idx(), 2 * NUM_PROPOSALS);
+    match leader_node.quorum_progress() {
+        QuorumProgress::Leader {
+            ballot,
+            phase,
+            responded,
+            missing,
+            ..
+        } => {
+            assert_eq!(ballot, leader_ballot);
+            assert_eq!(phase, RoundPhase::Accept);
+            assert_eq!(responded, others);
+            assert_eq!(missing, vec![blackholed]);
+        }
+        p => panic!("{}", format!("Leader returned a follower view: {:?}", p)),
+    }
+    match nodes[(blackholed - 1) as usize].quorum_progress() {
+        QuorumProgress::Follower {
+            accepted_round,
+            accepted_idx,
+            ..
+        } => {
+            // the follower only accepted the first batch
+            assert_eq!(accepted_round, leader_ballot);
+            assert_eq!(accepted_idx, NUM_PROPOSALS);
+        }
+        p => panic!("{}", format!("Follower returned a leader view: {:?}", p)),
+    }
+
+    println!("Pass blackholed_after_decide_quorum_progress");
+}
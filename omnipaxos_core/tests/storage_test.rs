@@ -0,0 +1,72 @@
+pub mod utils;
+
+use commitlog::LogOptions;
+use omnipaxos_core::storage::Storage;
+use omnipaxos_storage::persistent_storage::{
+    PersistentStorage, PersistentStorageConfig, StorageErr,
+};
+use sled::Config;
+use std::panic;
+use utils::{create_temp_dir, LatestValue, Value};
+
+const NUM_INSTANCES: u64 = 3;
+const NUM_ENTRIES: u64 = 10;
+const COMMITLOG: &str = "/commitlog/";
+
+type TestStorage = PersistentStorage<Value, LatestValue>;
+
+/// Verifies that destroying an instance leaves the other instances in the same directory untouched,
+/// and that neither a live instance nor a destroyed instance can be opened by accident.
+#[test]
+fn destroy_instance_test() {
+    let base_path = create_temp_dir();
+    let instance_paths: Vec<String> = (1..=NUM_INSTANCES)
+        .map(|i| format!("{base_path}/instance{i}"))
+        .collect();
+    for path in &instance_paths {
+        let mut storage = TestStorage::open(storage_config(path));
+        storage.append_entries((1..=NUM_ENTRIES).map(Value).collect());
+        storage.set_decided_idx(NUM_ENTRIES);
+    }
+    assert_eq!(
+        TestStorage::list_instances(&base_path).expect("Failed to list instances"),
+        instance_paths
+    );
+
+    let destroyed_path = &instance_paths[1];
+    {
+        let _live_storage = TestStorage::open(storage_config(destroyed_path));
+        match TestStorage::destroy(storage_config(destroyed_path)) {
+            Err(StorageErr::InstanceLocked(_)) => {}
+            res => panic!("Destroyed a live instance: {:?}", res),
+        }
+    }
+    TestStorage::destroy(storage_config(destroyed_path)).expect("Failed to destroy instance");
+
+    let remaining_paths: Vec<String> = instance_paths
+        .iter()
+        .filter(|p| *p != destroyed_path)
+        .cloned()
+        .collect();
+    assert_eq!(
+        TestStorage::list_instances(&base_path).expect("Failed to list instances"),
+        remaining_paths
+    );
+    for path in &remaining_paths {
+        let storage = TestStorage::open(storage_config(path));
+        assert_eq!(storage.get_decided_idx(), NUM_ENTRIES);
+        assert_eq!(
+            storage.get_entries(0, NUM_ENTRIES),
+            (1..=NUM_ENTRIES).map(Value).collect::<Vec<_>>()
+        );
+    }
+    let reopened = panic::catch_unwind(|| TestStorage::open(storage_config(destroyed_path)));
+    assert!(reopened.is_err(), "Reopened a destroyed instance");
+
+    println!("Pass destroy_instance");
+}
+
+fn storage_config(path: &str) -> PersistentStorageConfig {
+    let log_opts = LogOptions::new(format!("{path}{COMMITLOG}"));
+    PersistentStorageConfig::with(path.to_string(), log_opts, Config::new())
+}
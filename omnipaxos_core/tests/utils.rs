@@ -4,7 +4,9 @@ use kompact::{config_keys::system, executors::crossbeam_workstealing_pool, prelu
 use omnipaxos_core::{
     ballot_leader_election::Ballot,
     messages::Message,
+    omni_paxos::OmniPaxos,
     storage::{Entry, Snapshot, StopSign, Storage},
+    util::{DecidedHash, NodeId},
 };
 use omnipaxos_storage::{
     memory_storage::MemoryStorage,
@@ -255,6 +257,20 @@ where
             StorageType::Memory(mem_s) => mem_s.get_snapshot(),
         }
     }
+
+    fn set_decided_hash(&mut self, hash: DecidedHash) {
+        match self {
+            StorageType::Persistent(persist_s) => persist_s.set_decided_hash(hash),
+            StorageType::Memory(mem_s) => mem_s.set_decided_hash(hash),
+        }
+    }
+
+    fn get_decided_hash(&self) -> Option<DecidedHash> {
+        match self {
+            StorageType::Persistent(persist_s) => persist_s.get_decided_hash(),
+            StorageType::Memory(mem_s) => mem_s.get_decided_hash(),
+        }
+    }
 }
 
 pub struct TestSystem {
@@ -579,7 +595,7 @@ pub mod omnireplica {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialOrd, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialOrd, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Value(pub u64);
 
 #[derive(Clone, Copy, Debug, Default, PartialOrd, PartialEq, Serialize, Deserialize)]
@@ -619,3 +635,60 @@ pub fn create_temp_dir() -> String {
     let dir_path = dir.path().to_path_buf();
     dir_path.to_string_lossy().to_string()
 }
+
+/// Creates the config of server `pid` in a cluster of the servers `1..=num_nodes`.
+pub fn create_node_config(pid: NodeId, num_nodes: u64) -> OmniPaxosConfig {
+    let mut op_config = OmniPaxosConfig::default();
+    op_config.pid = pid;
+    op_config.peers = (1..=num_nodes).filter(|p| *p != pid).collect();
+    op_config.configuration_id = 1;
+    op_config
+}
+
+/// Creates server `pid` in a cluster of the servers `1..=num_nodes`. Used with `exchange_messages` to run a cluster without kompact.
+pub fn create_node<B>(pid: NodeId, num_nodes: u64, storage: B) -> OmniPaxos<Value, LatestValue, B>
+where
+    B: Storage<Value, LatestValue>,
+{
+    create_node_config(pid, num_nodes).build(storage)
+}
+
+/// Delivers the outgoing messages of all nodes until no more messages are produced. `nodes[i]` must be the server with pid `i + 1`.
+/// Messages for which `deliver` returns false are dropped.
+pub fn exchange_messages<B, F>(nodes: &mut [OmniPaxos<Value, LatestValue, B>], deliver: F)
+where
+    B: Storage<Value, LatestValue>,
+    F: Fn(&Message<Value, LatestValue>) -> bool,
+{
+    loop {
+        let msgs: Vec<_> = nodes
+            .iter_mut()
+            .flat_map(|n| n.outgoing_messages())
+            .collect();
+        if msgs.is_empty() {
+            break;
+        }
+        for msg in msgs.into_iter().filter(|m| deliver(m)) {
+            let receiver = msg.get_receiver();
+            nodes[(receiver - 1) as usize].handle_incoming(msg);
+        }
+    }
+}
+
+/// Calls `election_timeout()` on all nodes `num_rounds` times and exchanges the messages in between, see `exchange_messages`.
+pub fn run_election_rounds<B, F>(
+    nodes: &mut [OmniPaxos<Value, LatestValue, B>],
+    num_rounds: usize,
+    deliver: F,
+) where
+    B: Storage<Value, LatestValue>,
+    F: Fn(&Message<Value, LatestValue>) -> bool,
+{
+    for _ in 0..num_rounds {
+        exchange_messages(nodes, &deliver);
+        for node in nodes.iter_mut() {
+            node.election_timeout();
+        }
+    }
+    exchange_messages(nodes, &deliver);
+}
@@ -1,6 +1,7 @@
 use omnipaxos_core::{
     ballot_leader_election::Ballot,
     storage::{Entry, Snapshot, StopSignEntry, Storage},
+    util::DecidedHash,
 };
 /// An in-memory storage implementation for SequencePaxos.
 #[derive(Clone)]
@@ -23,6 +24,8 @@ where
     snapshot: Option<S>,
     /// Stored StopSign
     stopsign: Option<StopSignEntry>,
+    /// Stored hash of the decided log
+    decided_hash: Option<DecidedHash>,
 }
 
 impl<T, S> Storage<T, S> for MemoryStorage<T, S>
@@ -116,6 +119,14 @@ where
     fn get_snapshot(&self) -> Option<S> {
         self.snapshot.clone()
     }
+
+    fn set_decided_hash(&mut self, hash: DecidedHash) {
+        self.decided_hash = Some(hash);
+    }
+
+    fn get_decided_hash(&self) -> Option<DecidedHash> {
+        self.decided_hash
+    }
 }
 
 impl<T: Entry, S: Snapshot<T>> Default for MemoryStorage<T, S> {
@@ -128,6 +139,7 @@ impl<T: Entry, S: Snapshot<T>> Default for MemoryStorage<T, S> {
             trimmed_idx: 0,
             snapshot: None,
             stopsign: None,
+            decided_hash: None,
         }
     }
 }
@@ -8,9 +8,10 @@ use commitlog::{
 use omnipaxos_core::{
     ballot_leader_election::Ballot,
     storage::{Entry, Snapshot, StopSign, StopSignEntry, Storage},
+    util::DecidedHash,
 };
 use serde::{Deserialize, Serialize};
-use std::{iter::FromIterator, marker::PhantomData};
+use std::{fs, io, iter::FromIterator, marker::PhantomData, path::Path};
 use zerocopy::{AsBytes, FromBytes};
 
 #[cfg(feature = "rocksdb")]
@@ -21,12 +22,14 @@ use sled::{Config, Db};
 const DEFAULT: &str = "/default_storage/";
 const COMMITLOG: &str = "/commitlog/";
 const DATABASE: &str = "/database/";
+const TOMBSTONE: &str = "/DESTROYED";
 const NPROM: &[u8] = b"NPROM";
 const ACC: &[u8] = b"ACC";
 const DECIDE: &[u8] = b"DECIDE";
 const TRIM: &[u8] = b"TRIM";
 const STOPSIGN: &[u8] = b"STOPSIGN";
 const SNAPSHOT: &[u8] = b"SNAPSHOT";
+const DECIDED_HASH: &[u8] = b"DECIDED_HASH";
 
 /// Wrapper struct that represents a `Ballot` type. Implements AsBytes and FromBytes.
 #[repr(packed)]
@@ -47,6 +50,23 @@ impl BallotStorage {
     }
 }
 
+/// Wrapper struct that represents a `DecidedHash` type. Implements AsBytes and FromBytes.
+#[repr(packed)]
+#[derive(Clone, Copy, AsBytes, FromBytes)]
+struct DecidedHashStorage {
+    idx: u64,
+    hash: u64,
+}
+
+impl DecidedHashStorage {
+    fn with(h: DecidedHash) -> Self {
+        DecidedHashStorage {
+            idx: h.idx,
+            hash: h.hash,
+        }
+    }
+}
+
 /// Wrapper struct that represents a `StopSignEntry` type. Implements Serialize and Deserialize.
 #[derive(Clone, Serialize, Deserialize)]
 struct StopSignEntryStorage {
@@ -181,6 +201,23 @@ impl Default for PersistentStorageConfig {
     }
 }
 
+/// An error returned when managing the instances of `PersistentStorage` on disk.
+#[derive(Debug)]
+pub enum StorageErr {
+    /// No instance exists in the given path.
+    NotFound(String),
+    /// The database of the instance in the given path could not be locked, e.g. because it is opened by a running `PersistentStorage`.
+    InstanceLocked(String),
+    /// Reading or removing the files of an instance failed.
+    IoError(io::Error),
+}
+
+impl From<io::Error> for StorageErr {
+    fn from(err: io::Error) -> Self {
+        StorageErr::IoError(err)
+    }
+}
+
 /// A persistent storage implementation, lets sequence paxos write the log
 /// and current state to disk. Log entries are serialized and de-serialized
 /// into slice of bytes when read or written from the log.
@@ -209,6 +246,9 @@ impl<T: Entry, S: Snapshot<T>> PersistentStorage<T, S> {
     /// Creates or opens an existing storage
     pub fn open(storage_config: PersistentStorageConfig) -> Self {
         let path = storage_config.path.expect("No path found in config");
+        if Path::new(&format!("{path}{TOMBSTONE}")).exists() {
+            panic!("Cannot open instance, it has been destroyed in {}", path);
+        }
 
         let commitlog =
             CommitLog::new(storage_config.commitlog_options).expect("Failed to create Commitlog");
@@ -251,6 +291,74 @@ impl<T: Entry, S: Snapshot<T>> PersistentStorage<T, S> {
 
         Self::open(storage_config)
     }
+
+    /// Deletes the commitlog and database of the closed instance in the path of `storage_config`.
+    /// A tombstone marker is written before anything is deleted, so an instance is either fully usable or
+    /// refused by `open()`. A destroy that was interrupted by a crash can be completed by calling this again.
+    /// Returns `StorageErr::InstanceLocked` without deleting anything if the database is opened by a running instance.
+    pub fn destroy(storage_config: PersistentStorageConfig) -> Result<(), StorageErr> {
+        let path = storage_config.path.expect("No path found in config");
+        let commitlog_path = format!("{path}{COMMITLOG}");
+        let database_path = format!("{path}{DATABASE}");
+        let tombstone_path = format!("{path}{TOMBSTONE}");
+
+        if !Path::new(&tombstone_path).exists() {
+            if !Path::new(&commitlog_path).exists() && !Path::new(&database_path).exists() {
+                return Err(StorageErr::NotFound(path));
+            }
+            // opening the database fails if its lock is held by a running instance. The probe keeps the lock
+            // until the tombstone is durable, so no instance can open the database in between.
+            let db_exists = Path::new(&database_path).exists();
+            #[cfg(feature = "rocksdb")]
+            let _rocksdb_probe = if db_exists {
+                Some(
+                    DB::open(&storage_config.rocksdb_options, &database_path)
+                        .map_err(|e| StorageErr::InstanceLocked(e.to_string()))?,
+                )
+            } else {
+                None
+            };
+            #[cfg(feature = "sled")]
+            let _sled_probe = if db_exists {
+                let opts = storage_config.sled_options.path(&database_path);
+                Some(Config::open(&opts).map_err(|e| StorageErr::InstanceLocked(e.to_string()))?)
+            } else {
+                None
+            };
+            let tombstone = fs::File::create(&tombstone_path)?;
+            tombstone.sync_all()?;
+            // persist the directory entry of the tombstone before anything is deleted
+            fs::File::open(&path)?.sync_all()?;
+        }
+
+        for dir in &[commitlog_path, database_path] {
+            match fs::remove_dir_all(dir) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the paths of the instances that are stored in the subdirectories of `base_path`.
+    /// Instances that have been destroyed are not included.
+    pub fn list_instances(base_path: &str) -> Result<Vec<String>, StorageErr> {
+        let mut instances = vec![];
+        for dir_entry in fs::read_dir(base_path)? {
+            let dir_path = dir_entry?.path();
+            if !dir_path.is_dir() {
+                continue;
+            }
+            let path = dir_path.to_string_lossy().to_string();
+            let is_instance = Path::new(&format!("{path}{COMMITLOG}")).exists()
+                || Path::new(&format!("{path}{DATABASE}")).exists();
+            if is_instance && !Path::new(&format!("{path}{TOMBSTONE}")).exists() {
+                instances.push(path);
+            }
+        }
+        instances.sort();
+        Ok(instances)
+    }
 }
 
 impl<T, S> Storage<T, S> for PersistentStorage<T, S>
@@ -588,6 +696,50 @@ where
         }
     }
 
+    fn set_decided_hash(&mut self, hash: DecidedHash) {
+        let hash_store = DecidedHashStorage::with(hash);
+        let hash_bytes = hash_store.as_bytes();
+        #[cfg(feature = "rocksdb")]
+        {
+            self.rocksdb
+                .put(DECIDED_HASH, hash_bytes)
+                .expect("Failed to set 'DECIDED_HASH'");
+        }
+        #[cfg(feature = "sled")]
+        {
+            self.sled
+                .insert(DECIDED_HASH, hash_bytes)
+                .expect("Failed to set 'DECIDED_HASH'");
+        }
+    }
+
+    fn get_decided_hash(&self) -> Option<DecidedHash> {
+        #[cfg(feature = "rocksdb")]
+        {
+            let decided_hash = self
+                .rocksdb
+                .get(DECIDED_HASH)
+                .expect("Failed to retrieve 'DECIDED_HASH'");
+            decided_hash.map(|hash_bytes| {
+                let h_store = DecidedHashStorage::read_from(hash_bytes.as_slice())
+                    .expect("Failed to deserialize the decided hash");
+                DecidedHash::with(h_store.idx, h_store.hash)
+            })
+        }
+        #[cfg(feature = "sled")]
+        {
+            let decided_hash = self
+                .sled
+                .get(DECIDED_HASH)
+                .expect("Failed to retrieve 'DECIDED_HASH'");
+            decided_hash.map(|hash_bytes| {
+                let h_store = DecidedHashStorage::read_from(hash_bytes.as_bytes())
+                    .expect("Failed to deserialize the decided hash");
+                DecidedHash::with(h_store.idx, h_store.hash)
+            })
+        }
+    }
+
     // TODO: A way to trim the commitlog without deleting and recreating the log
     fn trim(&mut self, trimmed_idx: u64) {
         let trimmed_log: Vec<T> = self.get_entries(trimmed_idx, self.commitlog.next_offset()); // get the log entries from 'trimmed_idx' to latest